@@ -17,15 +17,22 @@ fn main() -> Result<(), std::io::Error> {
     ).unwrap();
     
     // Stores information about structs, scenes, functions, and identifiers
-    let context = translate::validate(&mut ast, &input).map_err(|e| 
+    let context = translate::validate(&mut ast, &input, &env.options()).map_err(|e| 
         exit!(format!("Semantic Error: {}", e)) 
     ).unwrap();
 
+    for warning in context.warnings() {
+        println!("Warning: {}\n", warning);
+    }
+
     // Write AST to a file
     if env.save_ast {
         env.save_ast(&ast)?;
     }
 
+    // Describe the shader's interface for the host
+    env.save_reflection(translate::reflect::reflect(&context))?;
+
     // AST -> templates -> GLSL
     let output = translate::translate(&ast, &context);
 
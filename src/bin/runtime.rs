@@ -4,14 +4,34 @@ use sdf_lang::runtime::application::Application;
 fn main() {
     let args: Vec<String> = std::env::args().collect();
 
+    let usage = "Usage: 'runtime PATH [--lib-path DIR]... [--uniforms FILE]'";
+
     // TODO: Better errors
     // TODO: Specify OpenGL version
-    if args.len() != 2 {
-        sdf_lang::exit!(format!("Error: Expected only one argument\nUsage: 'runtime PATH'"));
+    if args.len() < 2 {
+        sdf_lang::exit!(format!("Error: Expected a shader path\n{}", usage));
+    }
+
+    let mut options = sdf_lang::translate::Options::default();
+    let mut uniform_file = None;
+
+    let mut index = 2;
+    while index < args.len() {
+        match (args[index].as_str(), args.get(index + 1)) {
+            ("--lib-path", Some(path)) => options.lib_paths.push(path.into()),
+            ("--uniforms", Some(path)) => uniform_file = Some(path.clone()),
+            _ => sdf_lang::exit!(format!("Error: Unexpected argument '{}'\n{}", args[index], usage)),
+        }
+
+        index += 2;
     }
 
     let mut app = Application::new((4, 5),
-        &args[1]);
+        &args[1], options);
+
+    if let Some(path) = uniform_file {
+        app.set_uniform_file(path);
+    }
 
     app.run();
-}
\ No newline at end of file
+}
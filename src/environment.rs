@@ -10,10 +10,33 @@ pub struct Environment {
     pub input_path: PathBuf,
     pub output_path: PathBuf,
     pub save_ast: bool,
+    /// `-D key=value` pairs (used by `@cfg` and `if const`)
+    pub defines: Vec<(String, String)>,
+    /// `--lib-path DIR` directories to search for imported libraries
+    pub lib_paths: Vec<PathBuf>,
+    /// `--reflect PATH` saves a JSON description of the shader's uniforms, textures, and outputs
+    pub reflect_path: Option<PathBuf>,
+    /// `--glsl-version N` selects the GLSL version to compile for
+    pub glsl_version: Option<u32>,
+    /// `-A LINT`, `-W LINT`, and `--deny LINT` lint levels, in order
+    pub lints: Vec<(crate::parse::ast::LintLevel, String)>,
+    /// `--edition YEAR` selects the language edition
+    pub edition: Option<u32>,
 }
 
 // TODO: save_output/ast have weird formatting on their path `println!`s
 impl Environment {
+    /// Compilation settings derived from the command line
+    pub fn options(&self) -> crate::translate::Options {
+        crate::translate::Options {
+            defines: self.defines.clone(),
+            lib_paths: self.lib_paths.clone(),
+            glsl_version: self.glsl_version,
+            lints: self.lints.clone(),
+            edition: self.edition,
+        }
+    }
+
     pub fn save_output(&self, output_glsl: String) -> Result<(), std::io::Error> {       
         if !self.output_path.parent().expect("Cannot write to executable directory").exists() {
             fs::create_dir(self.output_path.parent().unwrap())?;
@@ -27,6 +50,16 @@ impl Environment {
         Ok(())
     }
 
+    pub fn save_reflection(&self, reflection: String) -> Result<(), std::io::Error> {
+        if let Some(path) = &self.reflect_path {
+            fs::write(path, reflection)?;
+
+            println!("Reflection saved to {}", path.display());
+        }
+
+        Ok(())
+    }
+
     pub fn save_ast(&self, ast: &crate::parse::ast::AST) -> Result<(), std::io::Error> {
         let output_path = Path::new("./output");
 
@@ -57,6 +90,12 @@ impl Environment {
         let mut input: Option<PathBuf> = None;
         let mut output: Option<PathBuf> = None;
         let mut save_ast = false;
+        let mut defines = Vec::new();
+        let mut lib_paths = Vec::new();
+        let mut reflect_path = None;
+        let mut glsl_version = None;
+        let mut lints = Vec::new();
+        let mut edition = None;
 
         let mut index = 1;
         let num_args = args.len();
@@ -66,12 +105,30 @@ impl Environment {
                     exit!(
             "sdf-lang compiler usage:\n
             --help\t\tDisplay this message\n
+            --version\t\tDisplay the compiler and standard library version\n
             --input PATH\tSpecify the input file path\n
             --output PATH\tSpecify the output file path. Only specify the file to store in /output/FILE\n
-            --AST\t\tSave the AST to text file in output directory
+            --AST\t\tSave the AST to text file in output directory\n
+            -D KEY=VALUE\tDefine a key for '@cfg(KEY = \"VALUE\")' and 'if const' (may be repeated)\n
+            -A LINT\t\tAllow (silence) a lint, or every warning with 'warnings'\n
+            -W LINT\t\tReport a lint as a warning\n
+            --deny LINT\tReport a lint as an error, or every warning with 'warnings'\n
+            --lib-path DIR\tSearch DIR for imported libraries (may be repeated, searched before SDF_PATH)\n
+            --reflect PATH\tSave a JSON description of the shader's uniforms, textures, and outputs\n
+            --glsl-version N\tCompile for GLSL version N (430, 440, 450, or 460; defaults to 450)\n
+            --edition YEAR\tCompile with the rules of an edition (2025 allows assigning to any 'let'; defaults to 2026)
              ");
                 }
 
+                "--version" => {
+                    let modules = crate::stdlib::MODULES.iter()
+                        .map(|(name, _)| format!("{}::{}", crate::stdlib::ROOT, name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    exit!(format!("sdf-lang {}\nstandard library: {}", crate::stdlib::VERSION, modules));
+                }
+
                 "--input" => {
                     if input.is_some() {
                         exit!("Error: Input path is redefined");
@@ -118,6 +175,108 @@ impl Environment {
                     save_ast = true;
                 }
 
+                "-D" => {
+                    if let Some(define) = args.get(index + 1) {
+                        let mut split = define.splitn(2, '=');
+                        let key = split.next().unwrap();
+
+                        if let Some(value) = split.next() {
+                            if key.is_empty() {
+                                exit!(format!("Error: Define '{}' is missing a key", define));
+                            }
+
+                            defines.push((key.to_owned(), value.to_owned()));
+                        } else if crate::translate::lint::is_lint(key) {
+                            exit!(format!("Error: Defines must be of the form 'KEY=VALUE' (use '--deny {}' to deny the lint)", key));
+                        } else {
+                            exit!(format!("Error: Defines must be of the form 'KEY=VALUE' (found '{}')", define));
+                        }
+                    } else {
+                        exit!("Error: No define specified after '-D'");
+                    }
+
+                    // The next index is the define, so skip it
+                    index += 1;
+                }
+
+                "-A" | "-W" | "--deny" => {
+                    let level = match args[index].as_str() {
+                        "-A" => crate::parse::ast::LintLevel::Allow,
+                        "-W" => crate::parse::ast::LintLevel::Warn,
+                        _ => crate::parse::ast::LintLevel::Deny,
+                    };
+
+                    match args.get(index + 1) {
+                        Some(lint) if crate::translate::lint::is_lint(lint) => lints.push((level, lint.clone())),
+                        Some(lint) => exit!(format!("Error: Unknown lint '{}'", lint)),
+                        None => exit!(format!("Error: No lint specified after '{}'", args[index])),
+                    }
+
+                    // The next index is the lint, so skip it
+                    index += 1;
+                }
+
+                "--lib-path" => {
+                    if let Some(path) = args.get(index + 1) {
+                        let p = PathBuf::from(path);
+                        if p.is_dir() {
+                            lib_paths.push(p);
+                        } else {
+                            exit!(format!("Error: Library path '{}' is not a directory", path));
+                        }
+                    } else {
+                        exit!("Error: No library path specified");
+                    }
+
+                    // The next index is the path, so skip it
+                    index += 1;
+                }
+
+                "--reflect" => {
+                    if reflect_path.is_some() {
+                        exit!("Error: Reflection path is redefined");
+                    }
+
+                    if let Some(path) = args.get(index + 1) {
+                        reflect_path = Some(PathBuf::from(path));
+                    } else {
+                        exit!("Error: No reflection path specified");
+                    }
+
+                    // The next index is the path, so skip it
+                    index += 1;
+                }
+
+                "--glsl-version" => {
+                    if glsl_version.is_some() {
+                        exit!("Error: GLSL version is redefined");
+                    }
+
+                    match args.get(index + 1).map(|v| v.parse::<u32>()) {
+                        Some(Ok(version)) if crate::translate::GLSL_VERSIONS.contains(&version) => glsl_version = Some(version),
+                        Some(_) => exit!(format!("Error: Unsupported GLSL version '{}' (expected one of {:?})", args[index + 1], crate::translate::GLSL_VERSIONS)),
+                        None => exit!("Error: No GLSL version specified"),
+                    }
+
+                    // The next index is the version, so skip it
+                    index += 1;
+                }
+
+                "--edition" => {
+                    if edition.is_some() {
+                        exit!("Error: Edition is redefined");
+                    }
+
+                    match args.get(index + 1).map(|v| v.parse::<u32>()) {
+                        Some(Ok(year)) if crate::translate::EDITIONS.contains(&year) => edition = Some(year),
+                        Some(_) => exit!(format!("Error: Unknown edition '{}' (expected one of {:?})", args[index + 1], crate::translate::EDITIONS)),
+                        None => exit!("Error: No edition specified"),
+                    }
+
+                    // The next index is the edition, so skip it
+                    index += 1;
+                }
+
                 // Unknown
                 x => {
                     exit!(format!("Error: Unknown argument '{}'. Run with '--help' to see proper usage.", x));
@@ -143,6 +302,12 @@ impl Environment {
             input_path: input.unwrap(),
             output_path: output.unwrap(),
             save_ast,
+            defines,
+            lib_paths,
+            reflect_path,
+            glsl_version,
+            lints,
+            edition,
         }
     }
 }
\ No newline at end of file
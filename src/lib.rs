@@ -4,6 +4,7 @@ extern crate lalrpop_util;
 pub mod environment;
 pub mod parse;
 pub mod translate;
+pub mod stdlib;
 
 // #[cfg(runtime)]
 pub mod runtime;
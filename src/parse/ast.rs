@@ -1,5 +1,7 @@
 // AST enums, types, and structs
 
+use crate::parse::types::Type;
+
 /// AST root
 pub type AST = Vec<Item>;
 pub type Span = (usize, usize);
@@ -14,6 +16,10 @@ pub enum Item {
         // If not specified, return type will be "void"
         return_type: TypeSpecifier,
         statements: Vec<Statement>,
+        // Whether importers can access this (`pub fn`)
+        public: bool,
+        // The signature, up to the body
+        span: Span,
     },
     Scene {
         name: String,
@@ -23,6 +29,16 @@ pub enum Item {
         name: String,
         // "field: type = optional_default,"
         fields: Vec<(String, TypeSpecifier, Option<Expression>)>,
+        public: bool,
+        // The declaration, up to the fields
+        span: Span,
+    },
+    // "type name = ty;"
+    TypeAlias {
+        name: String,
+        ty: TypeSpecifier,
+        public: bool,
+        span: Span,
     },
     Implementation {
         struct_name: String,
@@ -30,16 +46,71 @@ pub enum Item {
         functions: Vec<Item>,
     },
     Import {
-        file_name: String,
+        // `import shapes::primitives;` -> ["shapes", "primitives"]
+        path: Vec<String>,
+        // `import path as alias;`. Defaults to the last segment of the path
+        alias: Option<String>,
+        span: Span,
+    },
+    Use {
+        // Alias of an imported module
+        module: String,
+        // `None` brings every public item into scope (`use module::*;`)
+        names: Option<Vec<String>>,
         span: Span,
     },
     Features {
         features: Vec<String>,
         span: Span,
     },
+    /// `uniforms { name: type = default, ... }`
+    Uniforms {
+        uniforms: Vec<UniformDeclaration>,
+        span: Span,
+    },
+    /// An item guarded by `@cfg(...)`. Resolved (and removed) before validation
+    Cfg {
+        predicate: CfgPredicate,
+        item: Box<Item>,
+        span: Span,
+    },
+    /// `@allow(...)`, `@warn(...)`, or `@deny(...)` on an item, or on the whole file when followed by `;`.
+    /// Resolved (and removed) before validation
+    Lint {
+        level: LintLevel,
+        lints: Vec<String>,
+        item: Option<Box<Item>>,
+        span: Span,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What happens when a lint finds something
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
 }
 
 #[derive(Debug, Clone)]
+/// Compile-time condition used by `@cfg(...)` and `if const`
+pub enum CfgPredicate {
+    /// `key` is true when the key was defined at all
+    Defined(String),
+    /// `key = "value"`
+    Equals {
+        key: String,
+        value: String,
+    },
+    /// `all(a, b, ...)`
+    All(Vec<CfgPredicate>),
+    /// `any(a, b, ...)`
+    Any(Vec<CfgPredicate>),
+    /// `not(a)`
+    Not(Box<CfgPredicate>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum FuncParamQualifier {
     In,
     Out,
@@ -52,25 +123,32 @@ pub struct SpannedExpression {
     pub span: (usize, usize),
 }
 
+// Each `ty` is `None` until the expression is validated
 #[derive(Debug, Clone)]
 pub enum Expression {
     Parenthesized(Box<Expression>),
     Literal(Literal),
+    // `ty` is the element type
     ArrayConstructor {
         expressions: Vec<Box<Expression>>,
-        ty: String,
+        ty: Option<Type>,
+    },
+    // `(a, b, ...)`. Typed as the generated struct of the tuple
+    Tuple {
+        expressions: Vec<Box<Expression>>,
+        ty: Option<Type>,
     },
     Identifier(String),
     Binary {
         lhs: Box<Expression>,
         operator: BinaryOperator,
         rhs: Box<Expression>,
-        ty: String,
+        ty: Option<Type>,
     },
     Unary {
         operator: UnaryOperator,
         expr: Box<Expression>,
-        ty: String,
+        ty: Option<Type>,
     },
     FunctionApply(FunctionApply),
     FunctionCall(FunctionCall),
@@ -80,7 +158,7 @@ pub enum Expression {
         if_block: Vec<Statement>,
         else_block: Option<Vec<Statement>>,
         else_if_block: Option<Box<Expression>>,
-        ty: String,
+        ty: Option<Type>,
     },
 }
 
@@ -89,14 +167,14 @@ pub struct FunctionApply {
     pub name: String,
     pub func_parameters: usize,
     pub parameters: Vec<Expression>,
-    pub ty: String,
+    pub ty: Option<Type>,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionCall {
     pub name: String,
     pub parameters: Vec<Expression>,
-    pub ty: String,
+    pub ty: Option<Type>,
 }
 
 #[derive(Debug, Clone)]
@@ -147,6 +225,19 @@ pub struct ConstDeclaration {
     pub ty: TypeSpecifier,
     // TODO: When constant expressions are implemented, this must be constant-checked
     pub value: SpannedExpression,
+    // Only meaningful for global constants
+    pub public: bool,
+    pub span: Span,
+}
+
+/// An entry of a `uniforms { ... }` block
+#[derive(Debug, Clone)]
+pub struct UniformDeclaration {
+    /// `@uniform(...)` annotations
+    pub tag: Option<Tag>,
+    pub ident: String,
+    pub ty: TypeSpecifier,
+    pub default: SpannedExpression,
 }
 
 #[derive(Debug, Clone)]
@@ -154,12 +245,25 @@ pub enum Statement {
     Let {
         tag: Option<Tag>,
         ident: String,
+        // `let mut`
+        mutable: bool,
         ty: Option<TypeSpecifier>,
         expression: Option<SpannedExpression>,
+        span: Span,
     },
     LetConstructor {
         ident: String,
+        mutable: bool,
         constructor: Constructor,
+        span: Span,
+    },
+    // `let (a, b): ty = expression;`
+    LetPattern {
+        pattern: Pattern,
+        ty: Option<TypeSpecifier>,
+        expression: SpannedExpression,
+        // Holds the value being destructured (named during validation)
+        temp: String,
     },
     Constant(ConstDeclaration),
     Assignment {
@@ -169,6 +273,7 @@ pub enum Statement {
     },
     Return {
         expression: Option<SpannedExpression>,
+        span: Span,
     },
     For {
         loop_var: String,
@@ -188,6 +293,24 @@ pub enum Statement {
         expression: Expression,
         span: Span,
     },
+    /// A statement guarded by `@cfg(...)`. Resolved before validation
+    Cfg {
+        predicate: CfgPredicate,
+        statement: Box<Statement>,
+        span: Span,
+    },
+    /// `if const predicate { ... } else { ... }`. Resolved into a `Block` before validation
+    IfConst {
+        predicate: CfgPredicate,
+        if_block: Vec<Statement>,
+        else_block: Option<Vec<Statement>>,
+        span: Span,
+    },
+    /// Nested scope. Only produced by resolving `if const`
+    Block {
+        statements: Vec<Statement>,
+        span: Span,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -195,7 +318,7 @@ pub struct Member {
     // ident.function().ident.function() etc.
     pub path: Vec<IdentOrFunction>,
     // The final item's type
-    pub ty: String,
+    pub ty: Option<Type>,
 }
 #[derive(Debug, Clone)]
 pub enum IdentOrFunction {
@@ -205,20 +328,73 @@ pub enum IdentOrFunction {
 
 #[derive(Debug, Clone)]
 /// A tag identifies variables which require CPU initialization or modification
-pub enum Tag {
+pub struct Tag {
+    pub kind: TagKind,
+    /// `@tag(key = value, ...)`
+    pub arguments: Vec<(String, TagValue)>,
+}
+
+impl Tag {
+    pub fn argument(&self, key: &str) -> Option<&TagValue> {
+        self.arguments.iter().find(|(k, _)| k == key).map(|(_, value)| value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TagKind {
     Uniform,
-    Texture2D,
+    Texture,
     Out,
     // TODO: What else would be needed?
 }
 
+impl std::fmt::Display for TagKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TagKind::Uniform => write!(f, "@uniform"),
+            TagKind::Texture => write!(f, "@texture"),
+            TagKind::Out => write!(f, "@out"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TagValue {
+    Literal(Literal),
+    String(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // `name`, or `_` to ignore the value. The type is set during validation
+    Binding {
+        ident: String,
+        ty: Option<TypeSpecifier>,
+        // `mut name`
+        mutable: bool,
+    },
+    // `(pattern, pattern, ...)`
+    Tuple(Vec<Pattern>),
+    // A tuple pattern matched against a vec (`let (x, y) = p.xy;`). Set during validation
+    Vector(Vec<Pattern>),
+    // `Name { field, field: pattern, .. }`
+    Struct {
+        name: String,
+        fields: Vec<(String, Pattern)>,
+        // Whether `..` ignores the remaining fields
+        rest: bool,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TypeSpecifier {
     Identifier(String),
     Array {
         ty: String,
         size: u32,
-    }
+    },
+    // `(type, type, ...)`. Replaced by the generated tuple struct during validation
+    Tuple(Vec<TypeSpecifier>),
 }
 
 impl TypeSpecifier {
@@ -230,6 +406,8 @@ impl TypeSpecifier {
         match self {
             TypeSpecifier::Identifier(ident) => ident,
             TypeSpecifier::Array { ty, size: _ } => ty,
+            // Only exists before validation
+            TypeSpecifier::Tuple(_) => "(tuple)",
         }
     }
 
@@ -237,6 +415,10 @@ impl TypeSpecifier {
         match self {
             TypeSpecifier::Identifier(ident) => ident.clone(),
             TypeSpecifier::Array { ty, size } => format!("{}[{}]", ty, size),
+            TypeSpecifier::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|t| t.as_string()).collect();
+                format!("({})", elements.join(", "))
+            }
         }
     }
 }
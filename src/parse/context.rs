@@ -3,23 +3,142 @@ use crate::parse::ast::TypeSpecifier;
 
 use super::glsl::castable;
 use super::glsl;
+use super::types::{tuple_string, Scalar, Type};
+
+use crate::translate::Options;
 
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 
 // See https://www.khronos.org/opengl/wiki/Data_Type_(GLSL)
 
 struct StructSignature {
     name: String,
     // Fields and types with optional defaults (field, type, default)
-    fields: Vec<(String, TypeSpecifier, Option<ast::Expression>)>,
+    fields: Vec<(String, Type, Option<ast::Expression>)>,
     has_implementation: bool,
+    /// Methods declared without `pub`, which only the module with the implementation can call
+    private_methods: HashSet<String>,
+    /// The module containing the implementation
+    implemented_in: PathBuf,
 }
 
 struct FunctionSignature {
     name: String,
     // (field_name, field_type)
-    parameters: Vec<(String, TypeSpecifier)>,
-    return_type: TypeSpecifier,
+    parameters: Vec<(String, Type)>,
+    /// How each parameter is passed (`in` unless given)
+    qualifiers: Vec<ast::FuncParamQualifier>,
+    return_type: Type,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ItemKind {
+    Function,
+    Struct,
+    TypeAlias,
+    Constant,
+}
+
+#[derive(Clone)]
+struct ModuleItem {
+    /// Name of the item in the generated GLSL
+    glsl_name: String,
+    kind: ItemKind,
+    public: bool,
+}
+
+/// Names visible from within a single `.sdf` file
+struct Namespace {
+    /// Prepended to the GLSL names of this module's items (empty for the shader itself)
+    prefix: String,
+    /// Items declared by this module
+    items: HashMap<String, ModuleItem>,
+    /// `import file_name as alias;` (alias -> canonical path of the module)
+    imports: HashMap<String, PathBuf>,
+    /// Items brought into scope via `use`
+    uses: HashMap<String, ModuleItem>,
+}
+
+impl Namespace {
+    fn new(prefix: &str) -> Self {
+        Namespace {
+            prefix: prefix.to_owned(),
+            items: HashMap::new(),
+            imports: HashMap::new(),
+            uses: HashMap::new(),
+        }
+    }
+}
+
+/// Every library loaded during a single compilation
+pub struct ModuleGraph {
+    /// Canonical path -> module name. Libraries are only parsed/validated once.
+    loaded: HashMap<PathBuf, String>,
+    /// GLSL prefixes given to modules so far
+    prefixes: HashSet<String>,
+    /// Files currently being validated (the shader first), used to report cycles
+    stack: Vec<(PathBuf, String)>,
+    /// Validated library ASTs, dependencies before their dependents
+    pub libraries: Vec<ast::AST>,
+}
+
+impl ModuleGraph {
+    fn new() -> Self {
+        ModuleGraph {
+            loaded: HashMap::new(),
+            prefixes: HashSet::new(),
+            stack: Vec::new(),
+            libraries: Vec::new(),
+        }
+    }
+
+    /// Module name of an already loaded library
+    pub fn loaded(&self, path: &Path) -> Option<&String> {
+        self.loaded.get(path)
+    }
+
+    /// Start loading a file. Errors if the file is already being loaded (an import cycle).
+    pub fn push(&mut self, path: PathBuf, module: &str) -> Result<(), String> {
+        if self.stack.iter().any(|(p, _)| *p == path) {
+            let chain = self.stack.iter()
+                .map(|(_, m)| m.as_str())
+                .chain(std::iter::once(module))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            return Err(format!("Cyclic import detected: {}", chain));
+        }
+
+        self.stack.push((path, module.to_owned()));
+
+        Ok(())
+    }
+
+    /// A GLSL prefix for a newly loaded module. Different files may have the same import path
+    /// (`x/helpers.sdf` and `y/helpers.sdf` are both `helpers` to their importers), so a number is appended when needed
+    pub fn unique_prefix(&mut self, prefix: &str) -> String {
+        let mut unique = prefix.to_owned();
+        let mut count = 1;
+
+        while !self.prefixes.insert(unique.clone()) {
+            count += 1;
+            unique = format!("{}_{}", prefix, count);
+        }
+
+        unique
+    }
+
+    /// Finish loading the most recently pushed file
+    pub fn pop(&mut self, ast: Option<ast::AST>) {
+        let (path, module) = self.stack.pop().unwrap();
+
+        if let Some(ast) = ast {
+            self.libraries.push(ast);
+            self.loaded.insert(path, module);
+        }
+    }
 }
 
 // TODO: Refactor type system to be like this
@@ -27,16 +146,30 @@ struct FunctionSignature {
 pub enum ScopeType {
     Global,
     Function {
-        return_type: TypeSpecifier,
+        return_type: Type,
     },
     If,
     Loop,
     Impl,
+    Block,
+}
+
+/// Whether a variable can be assigned to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mutability {
+    /// `let mut`, parameters, etc. (and any `let` before edition 2026)
+    Mutable,
+    /// `let` without `mut`
+    Immutable,
+    /// A binding without `mut` in a destructuring pattern (`let (a, b) = ...;`)
+    ImmutablePattern,
+    /// `const`, read-only built-in variables, and `in self`
+    Constant,
 }
 
 pub struct Scope {
-    // scope -> (name -> (type, is_const))
-    scopes: HashMap<usize, HashMap<String, (TypeSpecifier, bool)>>,
+    // scope -> (name -> (type, mutability))
+    scopes: HashMap<usize, HashMap<String, (Type, Mutability)>>,
 
     // "global", "loop", "if", "function", "scene", etc.
     scope_variants: Vec<ScopeType>,
@@ -63,7 +196,7 @@ impl Scope {
         self.scope_variants.contains(&ScopeType::Loop)
     }
 
-    pub fn expected_return_type(&self) -> Result<TypeSpecifier, String> {
+    pub fn expected_return_type(&self) -> Result<Type, String> {
         for scope in &self.scope_variants {
             if let ScopeType::Function {return_type: t} = scope {
                 return Ok(t.clone());
@@ -90,14 +223,25 @@ impl Scope {
         self.current -= 1;
     }
 
-    fn add_var_to_scope(&mut self, name: String, ty: TypeSpecifier, is_constant: bool) -> Result<(), String> {
-        if let Some(_old) = self.scopes.get_mut(&self.current).unwrap().insert(name.clone(), (ty.clone(), is_constant)) {
+    fn add_var_to_scope(&mut self, name: String, ty: Type, mutability: Mutability) -> Result<(), String> {
+        if let Some(_old) = self.scopes.get_mut(&self.current).unwrap().insert(name.clone(), (ty, mutability)) {
             Err(format!("Variable '{}' already exists in the current scope", name))
         } else {   
             Ok(())
         }
     }
 
+    /// Whether a variable exists in any non-global scope (parameters, locals, etc.)
+    pub fn is_local_var(&self, name: &str) -> bool {
+        for scope in 1..=self.current {
+            if self.scopes.get(&scope).unwrap().get(name).is_some() {
+                return true;
+            }
+        }
+
+        false
+    }
+
     pub fn is_var_in_scope(&self, name: &str) -> bool {
         for scope in 0..=self.current {
             if self.scopes.get(&scope).unwrap().get(name).is_some() {
@@ -108,9 +252,9 @@ impl Scope {
         false
     }
 
-    pub fn var_type(&self, name: &str) -> Result<&TypeSpecifier, String> {
+    pub fn var_type(&self, name: &str) -> Result<&Type, String> {
         for scope in 0..=self.current {
-            if let Some((ty, _is_const)) = self.scopes.get(&scope).unwrap().get(name) {
+            if let Some((ty, _mutability)) = self.scopes.get(&scope).unwrap().get(name) {
                 return Ok(ty);
             }
         }
@@ -118,10 +262,10 @@ impl Scope {
         Err(format!("Unknown identifier '{}'", name))
     }
 
-    pub fn is_var_constant(&self, name: &str) -> Result<bool, String> {
+    pub fn var_mutability(&self, name: &str) -> Result<Mutability, String> {
         for scope in 0..=self.current {
-            if let Some((_ty, is_const)) = self.scopes.get(&scope).unwrap().get(name) {
-                return Ok(*is_const);
+            if let Some((_ty, mutability)) = self.scopes.get(&scope).unwrap().get(name) {
+                return Ok(*mutability);
             }
         }
 
@@ -129,6 +273,90 @@ impl Scope {
     }
 }
 
+/// A sampler declared with `@texture`, bound by the host to a texture unit
+#[derive(Clone, Debug)]
+pub struct TextureBinding {
+    pub name: String,
+    /// `sampler2D`, `sampler3D`, `samplerCube`, or `sampler2DArray`
+    pub ty: String,
+    pub binding: u32,
+    /// Whether the shader reads it (see `Context::deactivate_unused`)
+    pub active: bool,
+}
+
+/// A shader output declared with `@out` (a varying for vertex shaders)
+#[derive(Clone, Debug)]
+pub struct OutputBinding {
+    pub name: String,
+    pub ty: TypeSpecifier,
+    pub location: u32,
+}
+
+impl OutputBinding {
+    /// Locations used by this output (a matrix uses one per column)
+    fn locations(&self) -> Range<u32> {
+        let count = match Type::from_specifier(&self.ty) {
+            Type::Matrix(shape) => shape.columns as u32,
+            _ => 1,
+        };
+        self.location..self.location + count
+    }
+}
+
+/// A uniform declared with `@uniform` or by a feature
+#[derive(Clone, Debug)]
+pub struct UniformBinding {
+    pub name: String,
+    pub ty: TypeSpecifier,
+    pub location: u32,
+    /// Whether the location was given with `@uniform(location = N)`
+    pub explicit: bool,
+    /// Constant initial value (features have none)
+    pub default: Option<ast::Expression>,
+    pub meta: UniformMeta,
+    /// Whether the shader reads it. Inactive uniforms are left out of the GLSL and are not bound
+    pub active: bool,
+}
+
+/// Annotations from `@uniform(min = 0., max = 10., label = "Size", ...)` for tools and hosts
+#[derive(Clone, Debug, Default)]
+pub struct UniformMeta {
+    /// Applies to every component
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Increment used by UI widgets
+    pub step: Option<f64>,
+    /// Display name
+    pub label: Option<String>,
+    /// `slider`, `drag`, `color`, or `checkbox`
+    pub widget: Option<String>,
+    /// What the value represents to the host (e.g. `direction`)
+    pub semantic: Option<String>,
+}
+
+impl UniformMeta {
+    /// Restricts a value set from outside of the shader to `min..=max`
+    pub fn clamp(&self, value: f64) -> f64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+impl UniformBinding {
+    /// Locations used by this uniform (an array uses one per element)
+    fn locations(&self) -> Range<u32> {
+        let count = match &self.ty {
+            TypeSpecifier::Array { size, .. } => *size,
+            _ => 1,
+        };
+        self.location..self.location + count
+    }
+}
+
+fn overlaps(a: Range<u32>, b: Range<u32>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
 #[derive(PartialEq, Clone)]
 pub enum ShaderType {
     Vertex,
@@ -150,20 +378,47 @@ pub struct Context {
     /// Primitives such as int, uint, bool, etc.
     primitive_types: HashSet<&'static str>,
 
-    /// Collection of user-declared uniforms, their types, and defaults
-    // TODO: Default value is not implemented yet
+    /// Alias name -> Aliased type (with all aliases resolved)
+    type_aliases: HashMap<String, TypeSpecifier>,
+
+    /// Constant name -> Validated value (of the latest declaration, for local constants)
+    constant_values: HashMap<String, ast::Expression>,
+
+    /// Generated tuple struct name -> Element types
+    tuples: HashMap<String, Vec<Type>>,
+    /// Tuple structs that still need to be added to the AST
+    pending_tuples: Vec<ast::Item>,
+    /// Number of compiler-generated variables so far
+    temporaries: usize,
+
+    /// Canonical path of a module -> Names declared/imported by that module
+    modules: HashMap<PathBuf, Namespace>,
+    /// The module currently being validated (an empty path is the shader itself)
+    current_module: PathBuf,
+    pub module_graph: ModuleGraph,
+
+    /// Collection of user-declared uniforms, their types, and defaults (in declaration order)
     // TODO: Implement the scope for tagged variables (and allow shadowing?)
-    uniforms: HashSet<(String, TypeSpecifier, /* DEFAULT VALUE HERE */)>,
-    outs: HashSet<(String, TypeSpecifier, /* DEFAULT VALUE HERE */)>,
+    uniforms: Vec<UniformBinding>,
+    /// Shader outputs in declaration order
+    outs: Vec<OutputBinding>,
+    /// Samplers in declaration order
+    textures: Vec<TextureBinding>,
 
     // TODO: This
     pub features: HashSet<String>,
 
     pub scopes: Scope,
+
+    /// Settings this context was compiled with (defines, etc.)
+    pub options: Options,
+
+    /// Problems that do not stop compilation (already located)
+    warnings: Vec<String>,
 }
 
 impl Context {
-    pub fn new(shader_type: &ShaderType) -> Self {
+    pub fn new(shader_type: &ShaderType, options: Options) -> Self {
         macro_rules! declare_primitive_types {
             ( $( $x:expr ),+ ) => {{
                     let mut types = HashSet::new();
@@ -175,11 +430,14 @@ impl Context {
         // TODO: vectors, arrays, and matrices will be treated uniquely
         // see http://www.shaderific.com/glsl-types
         let primitive_types = declare_primitive_types!(
-            "float", "double", "bool", "int", "uint", "sampler2D", "samplerCube",
+            "float", "double", "bool", "int", "uint",
+            "sampler2D", "sampler3D", "samplerCube", "sampler2DArray",
             "vec2", "vec3", "vec4", "ivec2", "ivec3", "ivec4", "bvec2", "bvec3", "bvec4",
-            "dvec2", "dvec3", "dvec4", "uvec2", "uvec3", "uvec4"
-            //,"mat2", "mat3", "mat4", "mat2x2", "mat2x3", "mat2x4", "mat3x2", "mat3x3", "mat3x4",
-            //"may4x2", "mat4x3", "mat4x4"
+            "dvec2", "dvec3", "dvec4", "uvec2", "uvec3", "uvec4",
+            "mat2", "mat3", "mat4", "mat2x2", "mat2x3", "mat2x4", "mat3x2", "mat3x3", "mat3x4",
+            "mat4x2", "mat4x3", "mat4x4",
+            "dmat2", "dmat3", "dmat4", "dmat2x2", "dmat2x3", "dmat2x4", "dmat3x2", "dmat3x3", "dmat3x4",
+            "dmat4x2", "dmat4x3", "dmat4x4"
         );
 
 
@@ -193,54 +451,29 @@ impl Context {
         // see http://www.shaderific.com/glsl-functions
         let functions = HashMap::new();
 
-        // TODO: HashSet does not save insertion order. This is probably not an issue, but look into it.
-        let mut uniforms = HashSet::new();
-        let mut outs = HashSet::new();
+        let uniforms = Vec::new();
+        let mut outs = Vec::new();
         // Note that this defaults to the global scope
         let mut scopes = Scope::new();
 
-        // TODO: Finish this once arrays are implemented
-        // https://www.khronos.org/opengl/wiki/Built-in_Variable_(GLSL)
-        match shader_type {
-            ShaderType::Vertex => {
-                // Inputs
-                scopes.add_var_to_scope( "gl_VertexID".to_owned(), TypeSpecifier::from_ident("int"), true).unwrap();
-                scopes.add_var_to_scope( "gl_InstanceID".to_owned(), TypeSpecifier::from_ident("int"), true).unwrap();
-                // Note: These are 4.60+
-                scopes.add_var_to_scope( "gl_DrawID".to_owned(), TypeSpecifier::from_ident("int"), true).unwrap();
-                scopes.add_var_to_scope( "gl_BaseVertex".to_owned(), TypeSpecifier::from_ident("int"), true).unwrap();
-                scopes.add_var_to_scope( "gl_BaseInstance".to_owned(), TypeSpecifier::from_ident("int"), true).unwrap();
-                
-                // Outputs
-                scopes.add_var_to_scope( "gl_Position".to_owned(), TypeSpecifier::from_ident("vec4"), false).unwrap();
-                scopes.add_var_to_scope( "gl_PointSize".to_owned(), TypeSpecifier::from_ident("float"), false).unwrap();
-                // TODO: gl_ClipDistance
-            }
-
-            ShaderType::Fragment => {
-                // FIXME: This MUST be in out location 0 (must save this particular location)
-                outs.insert(("out_color".to_owned(), TypeSpecifier::from_ident("vec4")));
-                scopes.add_var_to_scope( "out_color".to_owned(),    TypeSpecifier::from_ident("vec4"), false).unwrap();
-                scopes.add_var_to_scope( "gl_FragCoord".to_owned(), TypeSpecifier::from_ident("vec4"), true).unwrap();
-                
-                // TODO: Add the rest
-            }
-            
-            ShaderType::Compute => {
-                scopes.add_var_to_scope( "gl_NumWorkGroups".to_owned(), TypeSpecifier::from_ident("uvec3"), true).unwrap();
-                scopes.add_var_to_scope( "gl_WorkGroupID".to_owned(), TypeSpecifier::from_ident("uvec3"), true).unwrap();
-                scopes.add_var_to_scope( "gl_LocalInvocationID".to_owned(), TypeSpecifier::from_ident("uvec3"), true).unwrap();
-                scopes.add_var_to_scope( "gl_GlobalInvocationID".to_owned(), TypeSpecifier::from_ident("uvec3"), true).unwrap();
-                scopes.add_var_to_scope( "gl_LocalInvocationIndex".to_owned(), TypeSpecifier::from_ident("uint"), true).unwrap();
-                
-                // GLSL 4.30+
-                scopes.add_var_to_scope( "gl_WorkGroupSize".to_owned(), TypeSpecifier::from_ident("uvec3"), true).unwrap();
-
-                // TODO: Add the rest
-            }
+        // The shader itself is not prefixed
+        let mut modules = HashMap::new();
+        modules.insert(PathBuf::new(), Namespace::new(""));
+
+        // The color output is always at location 0
+        if *shader_type == ShaderType::Fragment {
+            outs.push(OutputBinding {
+                name: "out_color".to_owned(),
+                ty: TypeSpecifier::from_ident("vec4"),
+                location: 0,
+            });
+            scopes.add_var_to_scope("out_color".to_owned(), Type::Vector(Scalar::Float, 4), Mutability::Mutable).unwrap();
+        }
 
-            ShaderType::Library => {
-                // Nothing to do here
+        // Built-in variables newer than the target version are left out (see `glsl::variables::unavailable`)
+        for variable in glsl::variables::builtin_variables(shader_type) {
+            if variable.since <= options.glsl_version() {
+                scopes.add_var_to_scope(variable.name.to_owned(), variable.ty(), if variable.writable { Mutability::Mutable } else { Mutability::Constant }).unwrap();
             }
         }
 
@@ -253,10 +486,21 @@ impl Context {
             functions,
             structs: HashMap::new(),
             primitive_types,
+            type_aliases: HashMap::new(),
+            constant_values: HashMap::new(),
+            tuples: HashMap::new(),
+            pending_tuples: Vec::new(),
+            temporaries: 0,
+            modules,
+            current_module: PathBuf::new(),
             uniforms,
             outs,
+            textures: Vec::new(),
             features: HashSet::new(),
             scopes,
+            options,
+            module_graph: ModuleGraph::new(),
+            warnings: Vec::new(),
         }
     }
 
@@ -271,18 +515,18 @@ impl Context {
 
         match feature {
             "time" => {
-                self.uniforms.insert((feature.to_owned(), TypeSpecifier::from_ident("float")));
-                self.scopes.add_var_to_scope( feature.to_owned(), TypeSpecifier::from_ident("float"), false).unwrap();    
+                self.declare_uniform(feature.to_owned(), TypeSpecifier::from_ident("float"), None, UniformMeta::default())?;
+                self.scopes.add_var_to_scope( feature.to_owned(), Type::FLOAT, Mutability::Constant).unwrap();    
             }
 
             "window_dimensions" => {
-                self.uniforms.insert((feature.to_owned(), TypeSpecifier::from_ident("vec2")));
-                self.scopes.add_var_to_scope( feature.to_owned(), TypeSpecifier::from_ident("vec2"), false).unwrap();    
+                self.declare_uniform(feature.to_owned(), TypeSpecifier::from_ident("vec2"), None, UniformMeta::default())?;
+                self.scopes.add_var_to_scope( feature.to_owned(), Type::Vector(Scalar::Float, 2), Mutability::Constant).unwrap();    
             }
             
             "mouse_position" => {
-                self.uniforms.insert((feature.to_owned(), TypeSpecifier::from_ident("vec2")));
-                self.scopes.add_var_to_scope( feature.to_owned(), TypeSpecifier::from_ident("vec2"), false).unwrap();    
+                self.declare_uniform(feature.to_owned(), TypeSpecifier::from_ident("vec2"), None, UniformMeta::default())?;
+                self.scopes.add_var_to_scope( feature.to_owned(), Type::Vector(Scalar::Float, 2), Mutability::Constant).unwrap();    
             }
 
             _ => {
@@ -293,55 +537,511 @@ impl Context {
         Ok(())
     }
 
-    pub fn add_var_to_scope(&mut self, name: String, ty: TypeSpecifier, is_constant: bool) -> Result<(), String> {
+    pub fn add_var_to_scope(&mut self, name: String, ty: Type, mutability: Mutability) -> Result<(), String> {
         if self.is_primitive(&name) {
             return Err(format!("Cannot name variable as primitive type '{}'", name));
         }
 
-        self.scopes.add_var_to_scope(name, ty.clone(), is_constant)?;
+        self.scopes.add_var_to_scope(name, ty, mutability)?;
 
         Ok(())
     }
 
-    pub fn is_primitive(&self, type_name: &str) -> bool {
-        self.primitive_types.contains(type_name)
+    /// Mutability of a `let` binding. Older editions allow assigning to any `let`
+    pub fn let_mutability(&self, mutable: bool) -> Mutability {
+        if mutable || self.options.edition() < crate::translate::IMMUTABLE_LET_EDITION {
+            Mutability::Mutable
+        } else {
+            Mutability::Immutable
+        }
     }
 
-    pub fn declare_uniform(&mut self, name: String, ty: TypeSpecifier /*, initial_value: ?? */) -> Result<(), String> {
-        if !self.uniforms.insert((name.clone(), ty.clone())) {
-            Err(format!("Uniform '{}' was already declared", &name))
+    /// Mutability of a binding in a destructuring pattern
+    pub fn pattern_mutability(&self, mutable: bool) -> Mutability {
+        match self.let_mutability(mutable) {
+            Mutability::Immutable => Mutability::ImmutablePattern,
+            mutability => mutability,
+        }
+    }
+
+    /// Begin validating an imported module. Returns the module to pass to `exit_module`.
+    pub fn enter_module(&mut self, module: &Path, prefix: &str) -> PathBuf {
+        self.modules.entry(module.to_owned()).or_insert_with(|| Namespace::new(prefix));
+
+        std::mem::replace(&mut self.current_module, module.to_owned())
+    }
+
+    pub fn exit_module(&mut self, previous: PathBuf) {
+        self.current_module = previous;
+    }
+
+    /// Prepended to the GLSL names of the current module's items (empty for the shader itself)
+    pub fn module_prefix(&self) -> &str {
+        &self.namespace().prefix
+    }
+
+    fn namespace(&self) -> &Namespace {
+        self.modules.get(&self.current_module).unwrap()
+    }
+
+    fn namespace_mut(&mut self) -> &mut Namespace {
+        self.modules.get_mut(&self.current_module).unwrap()
+    }
+
+    /// Declare an item in the current module, returning its (mangled) GLSL name
+    pub fn declare_item(&mut self, name: &str, kind: ItemKind, public: bool) -> Result<String, String> {
+        let namespace = self.namespace_mut();
+
+        let glsl_name = if namespace.prefix.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}__{}", namespace.prefix, name)
+        };
+
+        if namespace.items.contains_key(name) || namespace.uses.contains_key(name) {
+            return Err(format!("The name '{}' was declared multiple times", name));
+        }
+
+        namespace.items.insert(name.to_owned(), ModuleItem {
+            glsl_name: glsl_name.clone(),
+            kind,
+            public,
+        });
+
+        Ok(glsl_name)
+    }
+
+    /// Make `module` accessible from the current module as `alias::name`
+    pub fn import_module(&mut self, alias: &str, module: &Path) -> Result<(), String> {
+        if let Some(_old) = self.namespace_mut().imports.insert(alias.to_owned(), module.to_owned()) {
+            Err(format!("A module was already imported as '{}'", alias))
         } else {
             Ok(())
         }
     }
 
-    pub fn uniforms(&self) -> &HashSet<(String, TypeSpecifier)> {
+    /// `use alias::{names}`, or `use alias::*` if `names` is `None`
+    pub fn use_items(&mut self, alias: &str, names: Option<&[String]>) -> Result<(), String> {
+        let module = self.imported_module(alias)?;
+
+        let items: Vec<(String, ModuleItem)> = match names {
+            Some(names) => {
+                let mut items = Vec::new();
+                for name in names {
+                    let item = module.items.get(name).ok_or(format!("Module '{}' has no item named '{}'", alias, name))?;
+                    if !item.public {
+                        return Err(format!("'{}' is private to module '{}' (declare it with 'pub' to use it)", name, alias));
+                    }
+                    items.push((name.clone(), item.clone()));
+                }
+                items
+            }
+
+            None => {
+                module.items.iter()
+                    .filter(|(_name, item)| item.public)
+                    .map(|(name, item)| (name.clone(), item.clone()))
+                    .collect()
+            }
+        };
+
+        let namespace = self.namespace_mut();
+        for (name, item) in items {
+            if namespace.items.contains_key(&name) || namespace.uses.contains_key(&name) {
+                return Err(format!("Cannot use '{}::{}' because the name '{}' is already declared", alias, name, name));
+            }
+
+            namespace.uses.insert(name, item);
+        }
+
+        Ok(())
+    }
+
+    /// GLSL prefix of the module imported as `alias` by the current module
+    pub fn imported_prefix(&self, alias: &str) -> Result<&str, String> {
+        Ok(&self.imported_module(alias)?.prefix)
+    }
+
+    /// How the current module refers to an item, given its GLSL name (`alias::name` for imported items).
+    /// Methods are named `Struct.method`. Used to describe code in diagnostics
+    pub fn source_name(&self, glsl_name: &str) -> String {
+        if let Some(method) = glsl_name.strip_prefix("__") {
+            if let Some((struct_name, method)) = method.rsplit_once("__") {
+                return format!("{}.{}", self.source_name(struct_name), method);
+            }
+        }
+
+        let namespace = self.namespace();
+        let find = |items: &HashMap<String, ModuleItem>| items.iter().find(|(_, item)| item.glsl_name == glsl_name).map(|(name, _)| name.clone());
+
+        find(&namespace.items)
+            .or_else(|| find(&namespace.uses))
+            .or_else(|| namespace.imports.iter().find_map(|(alias, module)|
+                find(&self.modules.get(module)?.items).map(|name| format!("{}::{}", alias, name))
+            ))
+            .unwrap_or_else(|| glsl_name.to_owned())
+    }
+
+    fn imported_module(&self, alias: &str) -> Result<&Namespace, String> {
+        match self.namespace().imports.get(alias) {
+            Some(module) => self.modules.get(module).ok_or(format!("Module '{}' ({}) has not been loaded", alias, module.display())),
+            None => Err(format!("No module was imported as '{}'", alias)),
+        }
+    }
+
+    /// Finds an item by its user-facing name (`name` or `module::name`)
+    fn lookup_item(&self, name: &str) -> Result<Option<&ModuleItem>, String> {
+        let mut path = name.splitn(2, "::");
+        let first = path.next().unwrap();
+
+        if let Some(item_name) = path.next() {
+            let module = self.imported_module(first)?;
+
+            match module.items.get(item_name) {
+                Some(item) => {
+                    if item.public {
+                        Ok(Some(item))
+                    } else {
+                        Err(format!("'{}' is private to module '{}' (declare it with 'pub' to use it)", item_name, first))
+                    }
+                }
+
+                None => Err(format!("Module '{}' has no item named '{}'", first, item_name)),
+            }
+        } else {
+            let namespace = self.namespace();
+            Ok(namespace.items.get(name).or(namespace.uses.get(name)))
+        }
+    }
+
+    fn resolve_item(&self, name: &str, kind: ItemKind) -> Result<Option<String>, String> {
+        match self.lookup_item(name)? {
+            Some(item) => {
+                if item.kind == kind {
+                    Ok(Some(item.glsl_name.clone()))
+                } else {
+                    Err(format!("'{}' is a {:?}, but a {:?} was expected", name, item.kind, kind))
+                }
+            }
+
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the GLSL name of a user function
+    pub fn resolve_function_name(&self, name: &str) -> Result<String, String> {
+        match self.resolve_item(name, ItemKind::Function)? {
+            Some(glsl_name) => Ok(glsl_name),
+            None => Err(format!("The function '{}' was not found", name)),
+        }
+    }
+
+    /// Returns the GLSL type a type name refers to (aliases are replaced by their type)
+    fn resolve_named_type(&self, name: &str) -> Result<TypeSpecifier, String> {
+        if self.is_primitive(name) {
+            return Ok(TypeSpecifier::from_ident(name));
+        }
+
+        match self.lookup_item(name)? {
+            Some(item) if item.kind == ItemKind::Struct => Ok(TypeSpecifier::from_ident(&item.glsl_name)),
+            Some(item) if item.kind == ItemKind::TypeAlias => Ok(self.type_aliases[&item.glsl_name].clone()),
+            Some(item) => Err(format!("'{}' is a {:?}, but a type was expected", name, item.kind)),
+            None => Err(format!("Unknown or undeclared type '{}'", name)),
+        }
+    }
+
+    /// Returns the GLSL name of a type
+    pub fn resolve_type_name(&self, name: &str) -> Result<String, String> {
+        match self.resolve_named_type(name)? {
+            TypeSpecifier::Identifier(glsl_name) => Ok(glsl_name),
+            array => Err(format!("'{}' is an alias of the array type '{}', which cannot be used here", name, array.as_string())),
+        }
+    }
+
+    /// Returns the type with its name replaced by the GLSL name
+    pub fn resolve_type(&mut self, ty: &TypeSpecifier) -> Result<TypeSpecifier, String> {
+        Ok(match ty {
+            TypeSpecifier::Identifier(name) => self.resolve_named_type(name)?,
+            TypeSpecifier::Array { ty, size } => TypeSpecifier::Array {
+                ty: self.resolve_type_name(ty).map_err(|e| format!("{} (arrays of arrays are not supported)", e))?,
+                size: *size,
+            },
+            TypeSpecifier::Tuple(elements) => {
+                let mut resolved = Vec::new();
+                for element in elements {
+                    resolved.push(Type::from_specifier(&self.resolve_type(element)?));
+                }
+
+                self.tuple_type(resolved)?.specifier()
+            }
+        })
+    }
+
+    /// Returns the struct generated for a tuple of the given types.
+    /// Each shape of tuple only generates one struct.
+    pub fn tuple_type(&mut self, elements: Vec<Type>) -> Result<Type, String> {
+        let names: Vec<String> = elements.iter()
+            .map(|t| t.to_string().replace('[', "_").replace(']', ""))
+            .collect();
+        let name = format!("__tuple__{}", names.join("__"));
+
+        if !self.tuples.contains_key(&name) {
+            let fields: Vec<(String, TypeSpecifier, Option<ast::Expression>)> = elements.iter()
+                .enumerate()
+                .map(|(i, t)| (format!("_{}", i), t.specifier(), None))
+                .collect();
+
+            self.declare_struct(name.clone(), &fields)?;
+            self.tuples.insert(name.clone(), elements);
+            self.pending_tuples.push(ast::Item::Struct {
+                name: name.clone(),
+                fields,
+                public: true,
+                span: (0, 0),
+            });
+        }
+
+        Ok(Type::Struct(name))
+    }
+
+    /// Element types if `ty` is a tuple
+    pub fn tuple_elements(&self, ty: &Type) -> Option<&Vec<Type>> {
+        match ty {
+            Type::Struct(name) => self.tuples.get(name),
+            _ => None,
+        }
+    }
+
+    /// Structs generated for tuples since this was last called
+    pub fn take_tuple_structs(&mut self) -> Vec<ast::Item> {
+        std::mem::take(&mut self.pending_tuples)
+    }
+
+    /// Unique name for a compiler-generated variable
+    pub fn temporary(&mut self, purpose: &str) -> String {
+        self.temporaries += 1;
+        format!("__{}{}", purpose, self.temporaries)
+    }
+
+    /// Register `type name = ty;`, where `name` is the alias' GLSL name and `ty` is already resolved
+    pub fn declare_type_alias(&mut self, name: String, ty: TypeSpecifier) {
+        self.type_aliases.insert(name, ty);
+    }
+
+    /// Returns the aliased type name if `name` is an alias
+    fn unalias<'a>(&'a self, name: &'a str) -> &'a str {
+        match self.type_aliases.get(name) {
+            Some(ty) => ty.type_name(),
+            None => name,
+        }
+    }
+
+    /// Returns the GLSL name of a variable or constant.
+    /// Locals shadow module items, which shadow globals such as `gl_FragCoord`.
+    pub fn resolve_identifier(&self, name: &str) -> Result<String, String> {
+        if !name.contains("::") && self.scopes.is_local_var(name) {
+            return Ok(name.to_owned());
+        }
+
+        if let Some(glsl_name) = self.resolve_item(name, ItemKind::Constant)? {
+            return Ok(glsl_name);
+        }
+
+        if self.is_primitive(name) || self.scopes.is_var_in_scope(name) {
+            Ok(name.to_owned())
+        } else if let Some(reason) = self.unavailable_builtin(name) {
+            Err(reason)
+        } else {
+            Err(format!("Identifier '{}' not found in scope", name))
+        }
+    }
+
+    /// Why a built-in variable such as `gl_FragDepth` cannot be used by this shader (`None` for other names)
+    pub fn unavailable_builtin(&self, name: &str) -> Option<String> {
+        glsl::variables::unavailable(name, &self.shader_type, self.options.glsl_version())
+    }
+
+    pub fn is_primitive(&self, type_name: &str) -> bool {
+        self.primitive_types.contains(type_name)
+    }
+
+    /// Declares a uniform. Locations which are not given are chosen by `assign_uniform_locations`
+    pub fn declare_uniform(&mut self, name: String, ty: TypeSpecifier, location: Option<u32>, meta: UniformMeta) -> Result<(), String> {
+        if self.uniforms.iter().any(|u| u.name == name) {
+            return Err(format!("Uniform '{}' was already declared", &name));
+        }
+
+        let uniform = UniformBinding { name, ty, location: location.unwrap_or(0), explicit: location.is_some(), default: None, meta, active: true };
+
+        if uniform.explicit {
+            let other = self.uniforms.iter().find(|u| u.explicit && overlaps(u.locations(), uniform.locations()));
+            if let Some(other) = other {
+                return Err(format!("Uniform '{}' uses location {}, which is already used by '{}'", uniform.name, uniform.location, other.name));
+            }
+        }
+
+        self.uniforms.push(uniform);
+
+        Ok(())
+    }
+
+    pub fn set_constant_value(&mut self, name: &str, value: ast::Expression) {
+        self.constant_values.insert(name.to_owned(), value);
+    }
+
+    /// The value of a constant in scope (`None` for other variables, and for builtin constants)
+    pub fn constant_value(&self, name: &str) -> Option<&ast::Expression> {
+        match self.scopes.var_mutability(name) {
+            Ok(Mutability::Constant) => self.constant_values.get(name),
+            _ => None,
+        }
+    }
+
+    pub fn set_uniform_default(&mut self, name: &str, default: ast::Expression) {
+        if let Some(uniform) = self.uniforms.iter_mut().find(|u| u.name == name) {
+            uniform.default = Some(default);
+        }
+    }
+
+    /// Gives every uniform without an explicit location the lowest unused location (in declaration order).
+    /// Called once everything has been declared, so explicit locations never collide with chosen ones
+    pub fn assign_uniform_locations(&mut self) {
+        let mut assigned: Vec<Range<u32>> = self.uniforms.iter()
+            .filter(|u| u.explicit)
+            .map(|u| u.locations())
+            .collect();
+
+        for uniform in self.uniforms.iter_mut().filter(|u| !u.explicit) {
+            uniform.location = 0;
+            while assigned.iter().any(|r| overlaps(r.clone(), uniform.locations())) {
+                uniform.location += 1;
+            }
+
+            assigned.push(uniform.locations());
+        }
+    }
+
+    pub fn warn(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
+    }
+
+    pub fn uniforms(&self) -> &Vec<UniformBinding> {
         &self.uniforms
     }
 
     /// Fill a given HashMap with uniform information.
     /// name -> (location, type)
     pub fn get_uniform_map(&self, map: &mut HashMap<String, (usize, TypeSpecifier)>) {
-        let mut location = 0;
-        for (name, ty) in &self.uniforms {
-            map.insert(name.clone(), (location, ty.clone()));
-            location += 1;
+        for uniform in &self.uniforms {
+            map.insert(uniform.name.clone(), (uniform.location as usize, uniform.ty.clone()));
         }
     }
 
+    /// Only the features whose uniform is read by the shader
     pub fn get_features(&self, map: &mut HashSet<String>) {
-        *map = self.features.clone();
+        *map = self.features.iter().filter(|feature| self.is_active(feature)).cloned().collect();
+    }
+
+    /// Marks the uniforms and textures that are not in `used` as inactive
+    pub fn deactivate_unused(&mut self, used: &HashSet<String>) {
+        for uniform in self.uniforms.iter_mut() {
+            uniform.active = used.contains(&uniform.name);
+        }
+
+        for texture in self.textures.iter_mut() {
+            texture.active = used.contains(&texture.name);
+        }
+    }
+
+    /// Whether a uniform or texture is read by the shader
+    pub fn is_active(&self, name: &str) -> bool {
+        self.uniforms.iter().map(|u| (&u.name, u.active))
+            .chain(self.textures.iter().map(|t| (&t.name, t.active)))
+            .any(|(other, active)| other == name && active)
     }
     
-    pub fn declare_out(&mut self, name: String, ty: TypeSpecifier /*, initial_value: ?? */) -> Result<(), String> {
-        if !self.outs.insert((name.clone(), ty.clone())) {
-            Err(format!("Out '{}' was already declared", &name))
+    /// Declares a sampler. Without an explicit binding, the lowest unused texture unit is chosen
+    pub fn declare_texture(&mut self, name: String, ty: TypeSpecifier, binding: Option<u32>) -> Result<u32, String> {
+        let ty = self.validate_type(&ty)?;
+        if !ty.is_sampler() {
+            return Err(format!("Texture '{}' must be a sampler type ('sampler2D', 'sampler3D', 'samplerCube', or 'sampler2DArray'), not '{}'", name, ty));
+        }
+
+        if self.textures.iter().any(|t| t.name == name) {
+            return Err(format!("Texture '{}' was already declared", name));
+        }
+
+        let binding = match binding {
+            Some(binding) => {
+                if let Some(other) = self.textures.iter().find(|t| t.binding == binding) {
+                    return Err(format!("Texture '{}' uses binding {}, which is already used by '{}'", name, binding, other.name));
+                }
+
+                binding
+            }
+            None => (0..).find(|b| !self.textures.iter().any(|t| t.binding == *b)).unwrap(),
+        };
+
+        self.textures.push(TextureBinding { name, ty: ty.to_string(), binding, active: true });
+
+        Ok(binding)
+    }
+
+    /// Whether a name is a uniform or texture (including features), which only the CPU sets
+    pub fn is_uniform(&self, name: &str) -> bool {
+        self.uniforms.iter().any(|u| u.name == name) || self.textures.iter().any(|t| t.name == name)
+    }
+
+    pub fn textures(&self) -> &Vec<TextureBinding> {
+        &self.textures
+    }
+
+    /// Declares a shader output. Without an explicit location, the lowest unused location is chosen
+    pub fn declare_out(&mut self, name: String, ty: TypeSpecifier, location: Option<u32>) -> Result<u32, String> {
+        let type_name = self.validate_type(&ty)?;
+        let valid_type = match &type_name {
+            Type::Scalar(scalar) | Type::Vector(scalar, _) => {
+                *scalar != Scalar::Bool && *scalar != Scalar::Double
+            }
+            // Matrices can be passed between stages, but cannot be written to framebuffers
+            Type::Matrix(shape) => self.shader_type == ShaderType::Vertex && !shape.double,
+            _ => false,
+        };
+
+        match self.shader_type {
+            ShaderType::Compute => return Err(format!("Output '{}' cannot be declared because compute shaders have no outputs", name)),
+            ShaderType::Library => return Err(format!("Output '{}' cannot be declared in a library", name)),
+            _ if !valid_type => return Err(format!("Output '{}' cannot be of type '{}'", name, type_name)),
+            _ => {}
+        }
+
+        if self.outs.iter().any(|out| out.name == name) {
+            return Err(format!("Output '{}' was already declared", &name));
+        }
+
+        let mut out = OutputBinding { name, ty, location: location.unwrap_or(0) };
+
+        if location.is_some() {
+            if let Some(other) = self.outs.iter().find(|other| overlaps(out.locations(), other.locations())) {
+                return Err(format!("Output '{}' uses location {}, which is already used by '{}'", out.name, out.location, other.name));
+            }
         } else {
-            Ok(())
+            while self.outs.iter().any(|other| overlaps(out.locations(), other.locations())) {
+                out.location += 1;
+            }
         }
+
+        let location = out.location;
+        self.outs.push(out);
+
+        Ok(location)
     }
     
-    pub fn outs(&self) -> &HashSet<(String, TypeSpecifier)> {
+    pub fn outs(&self) -> &Vec<OutputBinding> {
         &self.outs
     }
 
@@ -361,6 +1061,8 @@ impl Context {
             name: name.clone(),
             fields,
             has_implementation: false,
+            private_methods: HashSet::new(),
+            implemented_in: PathBuf::new(),
         };
 
         if let Some(old) = self.structs.insert(name, signature) {
@@ -370,10 +1072,13 @@ impl Context {
         }
     }
 
-    pub fn declare_implementation(&mut self, struct_name: &str) -> Result<(), String> {
+    /// `private_methods` are the names of the methods declared without `pub`
+    pub fn declare_implementation(&mut self, struct_name: &str, private_methods: HashSet<String>) -> Result<(), String> {
         if let Some(signature) = self.structs.get_mut(struct_name) {
             if !signature.has_implementation {
                 signature.has_implementation = true;
+                signature.private_methods = private_methods;
+                signature.implemented_in = self.current_module.clone();
             } else {
                 return Err(format!("Struct '{}' already has an implementation.", struct_name));
             }
@@ -384,16 +1089,49 @@ impl Context {
         Ok(())
     }
 
-    pub fn struct_field_type(&self, struct_name: &str, field_name: &str) -> Result<&TypeSpecifier, String> {
-        if let Some(signature) = self.structs.get(struct_name) {
+    /// Private methods can only be called from the module that implements them
+    pub fn check_method_access(&self, struct_name: &str, method: &str) -> Result<(), String> {
+        match self.structs.get(struct_name) {
+            Some(signature) if signature.private_methods.contains(method) && signature.implemented_in != self.current_module => {
+                let module = self.namespace().imports.iter()
+                    .find(|(_, path)| **path == signature.implemented_in)
+                    .map(|(alias, _)| alias.clone())
+                    .unwrap_or_else(|| self.modules[&signature.implemented_in].prefix.clone());
+
+                Err(format!("'{}' is private to module '{}' (declare it with 'pub' to use it)", method, module))
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    pub fn struct_field_type(&self, ty: &Type, field_name: &str) -> Result<&Type, String> {
+        let signature = match ty {
+            Type::Struct(struct_name) => self.structs.get(struct_name),
+            _ => None,
+        };
+
+        if let Some(signature) = signature {
             for (name, ty, _default) in &signature.fields {
                 if name == field_name {
                     return Ok(ty);
                 }
             }
-            Err(format!("Struct '{}' does not have field '{}'", struct_name, field_name))
+            if let Some(elements) = self.tuple_elements(ty) {
+                return Err(format!("Tuple '{}' does not have element '{}'", tuple_string(elements), field_name.trim_start_matches('_')));
+            }
+
+            Err(format!("Struct '{}' does not have field '{}'", ty, field_name))
         } else {
-            Err(format!("Type '{}' is not a struct or does not exist (tried accessing field '{}')", struct_name, field_name))
+            Err(format!("Type '{}' is not a struct or does not exist (tried accessing field '{}')", ty, field_name))
+        }
+    }
+
+    /// Names of a struct's fields, in declaration order
+    pub fn struct_field_names(&self, struct_name: &str) -> Vec<String> {
+        match self.structs.get(struct_name) {
+            Some(signature) => signature.fields.iter().map(|(name, _ty, _default)| name.clone()).collect(),
+            None => Vec::new(),
         }
     }
 
@@ -404,27 +1142,27 @@ impl Context {
         
         let mut constructor: Vec<(String, ast::SpannedExpression)> = Vec::new();
         
-        let mut supplied = HashMap::new();
-        for (field_name, expr) in fields {
-            supplied.insert(field_name, expr);
-        }
-
         let mut all_fields = HashSet::new();
-        // Ensure no extra fields were given by the user
+        // Ensure no extra fields were given by the user (reported in the order they were given)
         for (field_name, _field_type, _default) in &signature.fields {
             all_fields.insert(field_name.clone());
         }
 
-        for field_name in supplied.keys() {
+        for (field_name, _expr) in &fields {
             if !all_fields.contains(field_name) {
                 return Err(format!("The struct '{}' has no field '{}'.", ty, field_name));
             }
         }
 
+        let mut supplied = HashMap::new();
+        for (field_name, expr) in fields {
+            supplied.insert(field_name, expr);
+        }
+
         for (field_name, field_type, default) in &signature.fields {
             if let Some(user_supplied) = supplied.get(field_name) {
                 // Ensure types are compatible
-                if !castable(&self.expression_type(&user_supplied.expression)?, &field_type.as_string())? {
+                if !castable(&self.expression_type(&user_supplied.expression)?, field_type) {
                     return Err(format!("The field '{}' on struct '{}' has type '{}', but got incompatible type '{}'", field_name, ty, field_type, self.expression_type(&user_supplied.expression)?));
                 }
 
@@ -443,7 +1181,7 @@ impl Context {
         Ok(constructor)
     }
 
-    pub fn declare_function(&mut self, name: String, declared_parameters: Vec<(Option<ast::FuncParamQualifier>, String, TypeSpecifier)>, return_type: TypeSpecifier) -> Result<(), String> {       
+    pub fn declare_function(&mut self, name: String, declared_parameters: Vec<(Option<ast::FuncParamQualifier>, String, TypeSpecifier)>, return_type: &TypeSpecifier) -> Result<(), String> {       
         if glsl::functions::is_builtin(&name) {
             return Err(format!("A builtin function, '{}' exists with the same name", &name));
         }
@@ -453,14 +1191,17 @@ impl Context {
         }
 
         let mut parameters = Vec::new();
-        for (_qual, parameter, ty) in declared_parameters {
+        let mut qualifiers = Vec::new();
+        for (qual, parameter, ty) in declared_parameters {
             parameters.push( (parameter.clone(), self.validate_type(&ty)?) );
+            qualifiers.push(qual.unwrap_or(ast::FuncParamQualifier::In));
         }
         
         let signature = FunctionSignature {
             name: name.clone(),
             parameters,
-            return_type,
+            qualifiers,
+            return_type: self.validate_return_type(return_type)?,
         };
         
         if let Some(old) = self.functions.insert(name, signature) {
@@ -472,213 +1213,145 @@ impl Context {
 
     // TODO: Force 2-parameter functions only (for sanity/feasability)
     // TODO: Do not allow vec constructors to pass through here
-    pub fn check_function_apply(&self, name: &str, passed_param_types: Vec<TypeSpecifier>) -> Result<(usize, TypeSpecifier), String> {
+    pub fn check_function_apply(&self, name: &str, passed_param_types: Vec<Type>) -> Result<(usize, Type), String> {
         if glsl::functions::is_builtin(name) {
-            let _ty = glsl::functions::can_arrow( name, &passed_param_types )?;
+            let _ty = glsl::functions::can_arrow(name, &passed_param_types, &self.shader_type, self.options.glsl_version())?;
             return Ok((2, passed_param_types[0].clone()));
         }
         
-        if let Some(signature) = self.functions.get(name) {   
+        if let Some(signature) = self.functions.get(name) {
             if signature.parameters.len() == 0 {
-                return Err(format!("The function '{}' does not accept any parameters", name));
-            } else if signature.return_type.as_string() == "void" {
-                return Err(format!("The function '{}' does not return anything (required for '<-' syntax)", name));
+                return Err(format!("The function '{}' does not accept any parameters", self.source_name(name)));
+            } else if signature.return_type == Type::Void {
+                return Err(format!("The function '{}' does not return anything (required for '<-' syntax)", self.source_name(name)));
+            } else if signature.qualifiers.iter().any(|qual| *qual != ast::FuncParamQualifier::In) {
+                return Err(format!("The function '{}' has 'out' or 'inout' parameters, so it cannot be used with '<-' syntax", self.source_name(name)));
             }
 
             if passed_param_types.len() < signature.parameters.len() {
                 return Err(format!("The function '{}' accepts {} parameters, but only {} were given (minimum of {} needed for '<-' syntax)",
-                                    name, signature.parameters.len(), passed_param_types.len(), signature.parameters.len()));
+                                    self.source_name(name), signature.parameters.len(), passed_param_types.len(), signature.parameters.len()));
             }
 
             let expected_type = &signature.parameters[0].1;
             if signature.return_type != *expected_type {
-                return Err(format!("The function '{}' returns a different type from what it accepts (must be same types for '<-' syntax)", name));
+                return Err(format!("The function '{}' returns a different type from what it accepts (must be same types for '<-' syntax)", self.source_name(name)));
             }
 
             for (_name, ty) in &signature.parameters {
                 if ty != expected_type {
-                    return Err(format!("The function '{}' accepts paremters of different types (must be same types for '<-' syntax)", name));
+                    return Err(format!("The function '{}' accepts paremters of different types (must be same types for '<-' syntax)", self.source_name(name)));
                 }
             }
             for ty in &passed_param_types {
                 if ty != expected_type {
-                    return Err(format!("The application of function '{}' requires parameters of type '{}', but got type '{}'", name, expected_type, ty));
+                    return Err(format!("The application of function '{}' requires parameters of type '{}', but got type '{}'", self.source_name(name), expected_type, ty));
                 }
             }
 
             Ok( (signature.parameters.len(), signature.return_type.clone()) )
         } else {
-            Err(format!("The function '{}' does not exist", name))
+            Err(format!("The function '{}' does not exist", self.source_name(name)))
+        }
+    }
+
+    /// How the argument at `index` is passed to a function or builtin (`In` for constructors)
+    pub fn parameter_qualifier(&self, name: &str, index: usize) -> ast::FuncParamQualifier {
+        if let Some(function) = self.functions.get(name) {
+            function.qualifiers.get(index).cloned().unwrap_or(ast::FuncParamQualifier::In)
+        } else if glsl::functions::writes_argument(name, index) {
+            ast::FuncParamQualifier::Out
+        } else {
+            ast::FuncParamQualifier::In
         }
     }
 
     /// Validates a function call, returning the function's type.
     /// Constructs vector types similarly.
-    pub fn check_function_call(&self, name: &str, passed_param_types: Vec<TypeSpecifier>) -> Result<TypeSpecifier, String> {
+    pub fn check_function_call(&self, name: &str, passed_param_types: Vec<Type>) -> Result<Type, String> {
         // None of these special cases can ever be arrays
-        if glsl::vec::is_vec_constructor_or_type(name) {
-            return glsl::vec::validate_constructor(name, &passed_param_types);
+        match Type::from_name(name) {
+            Some(Type::Vector(primitive, size)) => return glsl::vec::validate_constructor(primitive, size, &passed_param_types),
+            Some(Type::Matrix(shape)) => return glsl::mat::validate_constructor(shape, &passed_param_types),
+            _ => {}
         }
-        else if glsl::functions::is_builtin(name) {
-            return Ok(TypeSpecifier::Identifier(glsl::functions::validate_function(name, &passed_param_types)?));
+
+        if glsl::functions::is_builtin(name) {
+            return glsl::functions::validate_function(name, &passed_param_types, &self.shader_type, self.options.glsl_version());
         }
         
         if let Some(function) = self.functions.get(name) {
             if function.parameters.len() != passed_param_types.len() {
-                return Err(format!("The function '{}' takes {} parameters, but {} were supplied", name, function.parameters.len(), passed_param_types.len()));
+                return Err(format!("The function '{}' takes {} parameters, but {} were supplied", self.source_name(name), function.parameters.len(), passed_param_types.len()));
             }
 
             for ((param_name, param_type), passed_type) in function.parameters.iter().zip(passed_param_types.iter()) {
-                if !castable(&passed_type.as_string(), &param_type.as_string())? {
+                if !castable(passed_type, param_type) {
                     return Err(format!("The parameter '{}' in function '{}' takes a '{}', but a '{}' was given (cannot cast)",
-                                                        param_name, name, param_type, passed_type));
+                                                        param_name, self.source_name(name), param_type, passed_type));
                 }
             }
 
             Ok(function.return_type.clone())
         } else {
-            Err(format!("The function '{}' was not found", name))
+            Err(format!("The function '{}' was not found", self.source_name(name)))
         }
     }
 
-    pub fn add_type(&self, left_type: &str, right_type: &str) -> Result<String, String> {
-        // TODO: when the right_type is a vec type
-        let resulting = match left_type {
-            "double" => {
-                match right_type {
-                    "double" | "float" | "int" | "uint" => "double",
-                    _ => return Err(format!("Types '{}' and '{}' are incompatible or not implemented", left_type, right_type)),
-                }
-            }
-            
-            "float" => {
-                match right_type {
-                    "double" => "double",
-                    "float" | "int" | "uint" => "float",
-                    x @ "vec2" | x @ "vec3" | x @ "vec4" => x,
-                    _ => return Err(format!("Types '{}' and '{}' are incompatible or not implemented", left_type, right_type)),
-                }
-            }
-            
-            "int" => {
-                match right_type {
-                    "double" => "double",
-                    "float" => "float",
-                    "int" => "int",
-                    "uint" => "int",
-                    _ => return Err(format!("Types '{}' and '{}' are incompatible or not implemented", left_type, right_type)),
-                }
-            }
-
-            "uint" => {
-                match right_type {
-                    "double" => "double",
-                    "float" => "float",
-                    "int" => "int",
-                    "uint" => "uint",
-                    _ => return Err(format!("Types '{}' and '{}' are incompatible or not implemented", left_type, right_type)),
-                }
-            }
-
-            v @ "dvec2" | v @ "dvec3" | v @ "dvec4" => {
-                match right_type {
-                    "double" | "float" | "int" | "uint" => v,
-                    r => {
-                        if v == r {
-                            v
-                        } else {
-                            return Err(format!("Cannot add type '{}' to type '{}'", r, v));
-                        }
-                    }
-                    // _ => panic!(format!("Vector type '{}' must be left of added type '{}'", left_type, right_type))
-                }
-            }
-
-            v @ "vec2" | v @ "vec3" | v @ "vec4" => {
-                match right_type {
-                    "float" | "int" | "uint" => v,
-                    r => {
-                        if v == r {
-                            v
-                        } else {
-                            return Err(format!("Cannot add type '{}' to type '{}'", r, v));
-                        }
-                    }
-                    // _ => panic!(format!("Vector type '{}' must be left of added type '{}'", left_type, right_type))
-                }
-            }
+    /// Type of `value[index]`: an array element, a vec component, or a matrix column
+    pub fn index_type(&self, ty: &Type) -> Result<Type, String> {
+        match ty {
+            Type::Array(element, _) => Ok(element.as_ref().clone()),
+            Type::Vector(component, _) => Ok(Type::Scalar(*component)),
+            Type::Matrix(shape) => Ok(shape.column_type()),
 
-            v @ "ivec2" | v @ "ivec3" | v @ "ivec4" => {
-                match right_type {
-                    "int" | "uint" => v,
-                    r => {
-                        if v == r {
-                            v
-                        } else {
-                            return Err(format!("Cannot add type '{}' to type '{}'", r, v));
-                        }
-                    }
-                    // _ => panic!(format!("Vector type '{}' must be left of added type '{}'", left_type, right_type))
-                }
-            }
+            _ => Err(format!("Type '{}' cannot be indexed (only arrays, vectors, and matrices can)", ty)),
+        }
+    }
 
-            v @ "uvec2" | v @ "uvec3" | v @ "uvec4" => {
-                match right_type {
-                    "uint" => v,
-                    r => {
-                        if v == r {
-                            v
-                        } else {
-                            return Err(format!("Cannot add type '{}' to type '{}'", r, v));
-                        }
-                    }
-                    // _ => panic!(format!("Vector type '{}' must be left of added type '{}'", left_type, right_type))
-                }
-            }
+    pub fn negate_type(&self, ty: &Type) -> Result<Type, String> {
+        match ty {
+            Type::Scalar(Scalar::UInt) => Ok(Type::INT),
+            Type::Vector(Scalar::UInt, size) => Ok(Type::Vector(Scalar::Int, *size)),
 
-            _ => return Err(format!("Cannot add/subtract type '{}' with type '{}'", left_type, right_type)),
-        };
+            Type::Scalar(Scalar::Bool) | Type::Vector(Scalar::Bool, _) => Err("Cannot negate boolean types".to_owned()),
 
-        Ok(resulting.to_owned())
-    }
+            // double, float, int, vec2, vec3, vec4, matrices, etc.
+            _ if ty.is_numeric() => Ok(ty.clone()),
 
-    pub fn multiply_type(&self, left_type: &str, right_type: &str) -> Result<String, String> {
-        // TODO: Is it exactly the same?
-        self.add_type(left_type, right_type)
+            _ => Err("Only numeric types can be negated".to_owned()),
+        }
     }
 
-    pub fn negate_type(&self, type_name: &str) -> Result<String, String> {
-        match type_name {
-            "uint" => Ok("int".to_owned()),
-
-            "uvec2" => Ok("ivec2".to_owned()),
-            "uvec3" => Ok("ivec3".to_owned()),
-            "uvec4" => Ok("ivec4".to_owned()),
-
-            "bool" | "bvec2" | "bvec3" | "bvec4" => return Err("Cannot negate boolean types".to_owned()),
+    /// Returns the Type of a TypeSpecifier if it is of a valid type/array.
+    pub fn validate_type(&self, ty: &TypeSpecifier) -> Result<Type, String> {
+        if let (TypeSpecifier::Identifier(name), Some(aliased)) = (ty, self.type_aliases.get(ty.type_name())) {
+            return self.validate_type(aliased).map_err(|e| format!("{} (aliased by '{}')", e, name));
+        }
 
-            // double, float, int, vec2, vec3, vec4, etc.
-            x => {
-                if self.structs.contains_key(x) {
-                    return Err("Only numeric types can be negated".to_owned());
-                }
+        let name = self.unalias(ty.type_name());
+        if self.primitive_types.contains(name) || self.structs.contains_key(name) {
+            let element = Type::from_name(name).unwrap_or_else(|| Type::Struct(name.to_owned()));
 
-                // TODO: Need to check more types before this (bvecs, enums, etc.)
-                Ok(x.to_owned())
-            }
+            Ok(match ty {
+                TypeSpecifier::Array { size, .. } => Type::Array(Box::new(element), *size),
+                _ => element,
+            })
+        } else {
+            Err(format!("Unknown or undeclared type '{}'", ty.type_name()))
         }
     }
 
-    /// Returns the TypeSpecifier if it is of a valid type/array.
-    pub fn validate_type(&self, ty: &TypeSpecifier) -> Result<TypeSpecifier, String> {
-        if self.primitive_types.contains(ty.type_name()) || self.structs.contains_key(ty.type_name()) {
-            Ok(ty.clone())
-        } else {
-            Err(format!("Unknown or undeclared type '{}'", ty.type_name()))
+    /// `validate_type`, but functions may also return `void`
+    pub fn validate_return_type(&self, ty: &TypeSpecifier) -> Result<Type, String> {
+        match ty {
+            TypeSpecifier::Identifier(name) if name == "void" => Ok(Type::Void),
+            _ => self.validate_type(ty),
         }
     }
 
     pub fn validate_type_name(&self, name: &str) -> Result<(), String> {
+        let name = self.unalias(name);
         if self.primitive_types.contains(name) || self.structs.contains_key(name) {
             Ok(())
         } else {
@@ -686,10 +1359,16 @@ impl Context {
         }
     }
 
-    pub fn expression_type(&self, expression: &ast::Expression) -> Result<String, String>{
+    pub fn expression_type(&self, expression: &ast::Expression) -> Result<Type, String>{
+        let validated = |ty: &Option<Type>| ty.clone().ok_or_else(|| "The type of an expression was needed before it was validated".to_owned());
+
         Ok(match expression {
             ast::Expression::ArrayConstructor { expressions, ty } => {
-                format!("{}[{}]", ty, expressions.len())
+                Type::Array(Box::new(validated(ty)?), expressions.len() as u32)
+            }
+
+            ast::Expression::Tuple { expressions: _, ty } => {
+                validated(ty)?
             }
 
             ast::Expression::Parenthesized(expr) => {
@@ -698,67 +1377,36 @@ impl Context {
 
             ast::Expression::Literal(literal) => {
                 match literal {
-                    ast::Literal::Bool(_) => {
-                        "bool"
-                    }
-
-                    ast::Literal::Float(_) => {
-                        "float"
-                    }
-
-                    ast::Literal::Int(_) => {
-                        "int"
-                    }
-                    
-                    ast::Literal::UInt(_) => {
-                        "uint"
-                    }
-                    
-                    x => return Err(format!("Type of {:?} is not implemented yet", x)),
-                
-                }.to_owned()
+                    ast::Literal::Bool(_) => Type::BOOL,
+                    ast::Literal::Float(_) => Type::FLOAT,
+                    ast::Literal::Double(_) => Type::DOUBLE,
+                    ast::Literal::Int(_) => Type::INT,
+                    ast::Literal::UInt(_) => Type::UINT,
+                }
             }
 
             ast::Expression::Identifier(name) => {
-                self.scopes.var_type(name)?.as_string()
+                self.scopes.var_type(name)?.clone()
             }
 
-            ast::Expression::Binary {ty, ..} => {
-                ty.clone()
-            }
-
-            ast::Expression::Unary {operator, expr: _, ty} => {
-                match operator {
-                    ast::UnaryOperator::Index(_) => {
-                        // TODO: Is this correct?
-                        // TODO: Note that multi-dimensional arrays are version 4.30+ only
-                        
-                        // Strip the array type off, leaving only the base type
-                        // ty.split("[").take(1).collect::<Vec<&str>>()[0].to_owned()
-                        ty.split("[").next().unwrap().to_owned()
-                    }
-                    
-                    _ => {
-                        ty.clone()
-                    }
-                }
+            // Note that the type of an index is set to the element's type during validation
+            ast::Expression::Binary {ty, ..}
+            | ast::Expression::Unary {ty, ..}
+            | ast::Expression::If {ty, ..} => {
+                validated(ty)?
             }
 
             ast::Expression::FunctionApply(apply) => {
-                apply.ty.clone()
+                validated(&apply.ty)?
             }
 
             ast::Expression::FunctionCall(call) => {
-                call.ty.clone()
-            }
-
-            ast::Expression::If {ty, ..} => {
-                ty.clone()
+                validated(&call.ty)?
             }
 
             ast::Expression::Member(member) => {
-                member.ty.clone()
+                validated(&member.ty)?
             }
         })
     }
-}
\ No newline at end of file
+}
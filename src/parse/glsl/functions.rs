@@ -1,270 +1,539 @@
-use crate::parse::ast::TypeSpecifier;
+use crate::parse::context::ShaderType;
+use crate::parse::types::{tuple_string, Sampler, Scalar, Type};
 
-use super::castable;
+use super::mat::MatShape;
+use super::scalar_castable;
 
-// see http://www.shaderific.com/glsl-functions
+// Every builtin function of GLSL 4.50 (and 4.60) that works with the types sdf-lang supports.
+// Atomic counters, images, and geometry/tessellation functions are left out because their types and stages don't exist.
+// See chapter 8 of the GLSL 4.60 specification
 
-// TODO: If a function requires a float, and the passed value is castable to float,
-//       that still won't work because of GLSL's overloading (give this error message)
+// Note: No GLSL functions work with arrays
 
-// FIXME: A lot of this code could be simplified (lots of repetition)
+/// A parameter (or return type) of a builtin overload. Uses the placeholders of the specification.
+/// Within one overload, every generic parameter has the same number of components
+#[derive(Clone, Copy, Debug)]
+enum Param {
+    /// `genType`, `genDType`, `genIType`, `genUType`, and `genBType`: a scalar or a vector
+    Gen(Scalar),
+    /// `vec`, `dvec`, `ivec`, `uvec`, and `bvec`: a vector (2 to 4 components)
+    GenVec(Scalar),
+    /// An exact scalar, like the `float` of `mix(genType, genType, float)`
+    Scalar(Scalar),
+    /// An exact vector, like the `vec3`s of `cross`
+    Vector(Scalar, usize),
+    /// `mat` or `dmat` (any dimensions)
+    Mat(Scalar),
+    /// `mat` or `dmat` with as many columns as rows
+    SquareMat(Scalar),
+    /// The transpose of the overload's `Mat`
+    Transposed(Scalar),
+    /// A vector with one component per row of the overload's `Mat` (a column)
+    Column(Scalar),
+    /// A vector with one component per column of the overload's `Mat` (a row)
+    Row(Scalar),
+    Sampler(Sampler),
+    /// An `out` parameter. The argument must match exactly (it is written to, not converted)
+    Out(&'static Param),
+    Void,
+}
 
-// Note: No GLSL functions work with arrays
+use Param::*;
+
+const GEN: Param = Gen(Scalar::Float);
+const GEN_D: Param = Gen(Scalar::Double);
+const GEN_I: Param = Gen(Scalar::Int);
+const GEN_U: Param = Gen(Scalar::UInt);
+const GEN_B: Param = Gen(Scalar::Bool);
+const VEC: Param = GenVec(Scalar::Float);
+const DVEC: Param = GenVec(Scalar::Double);
+const IVEC: Param = GenVec(Scalar::Int);
+const UVEC: Param = GenVec(Scalar::UInt);
+const BVEC: Param = GenVec(Scalar::Bool);
+const FLOAT: Param = Param::Scalar(Scalar::Float);
+const DOUBLE: Param = Param::Scalar(Scalar::Double);
+const INT: Param = Param::Scalar(Scalar::Int);
+const UINT: Param = Param::Scalar(Scalar::UInt);
+const BOOL: Param = Param::Scalar(Scalar::Bool);
+const MAT: Param = Mat(Scalar::Float);
+const DMAT: Param = Mat(Scalar::Double);
+
+const VEC2: Param = Vector(Scalar::Float, 2);
+const VEC3: Param = Vector(Scalar::Float, 3);
+const VEC4: Param = Vector(Scalar::Float, 4);
+const DVEC3: Param = Vector(Scalar::Double, 3);
+const IVEC2: Param = Vector(Scalar::Int, 2);
+const IVEC3: Param = Vector(Scalar::Int, 3);
+const UVEC2: Param = Vector(Scalar::UInt, 2);
+
+const SAMPLER_2D: Param = Param::Sampler(Sampler::Sampler2D);
+const SAMPLER_3D: Param = Param::Sampler(Sampler::Sampler3D);
+const SAMPLER_CUBE: Param = Param::Sampler(Sampler::SamplerCube);
+const SAMPLER_2D_ARRAY: Param = Param::Sampler(Sampler::Sampler2DArray);
+
+/// `(return type, parameters)`
+type Overload = (Param, &'static [Param]);
+
+struct Builtin {
+    name: &'static str,
+    overloads: &'static [Overload],
+    /// The only stage that may call the function (`None` for every stage)
+    stage: Option<ShaderType>,
+    /// Minimum GLSL version
+    since: u32,
+}
 
-pub fn is_builtin(function: &str) -> bool {
-    match function {
-        "radians" | "degrees" | "sin" | "cos" | "tan" | "asin" | "acos" | "atan" |
-        "exp" | "log" | "exp2" | "log2" |"sqrt" | "inversesqrt" |"abs" | "sign" | 
-        "floor" | "ceil" | "fract" |"normalize" | "pow" | "mod" | "min" | "max" | 
-        "clamp" |"mix" | "step" | "smoothstep" | "distance" | "dot" | "cross" |
-        "faceforward" | "reflect" | "refract" | "matrixCompMult" | "lessThan" |
-        "lessThanEqual" |"greaterThan" | "greaterThanEqual" | "equal" | "notEqual" |
-        "any" | "all" | "not" |"texture2D" | "textureCube" | "length"
-           => true,
-
-         _ => false,
-    }
+const fn builtin(name: &'static str, since: u32, overloads: &'static [Overload]) -> Builtin {
+    Builtin { name, overloads, stage: None, since }
 }
 
-/// Workaround for overloaded methods within GLSL (sdf-lang does not support overloading)
-pub fn validate_function(function: &str, types: &Vec<TypeSpecifier>) -> Result<String, String> {   
-    let as_strings: Vec<String> = types.iter().map(|t| t.as_string()).collect();
-    
-    match types.len() {
-        0 => Err(format!("Error: '{}' does not accept zero parameters", function)),
-        
+const fn fragment(name: &'static str, since: u32, overloads: &'static [Overload]) -> Builtin {
+    Builtin { name, overloads, stage: Some(ShaderType::Fragment), since }
+}
 
-        1 => validate_single_param(function, &as_strings[0]).map(|t| t.to_owned()),
-        2 => validate_two_params(function, &as_strings).map(|t| t.to_owned()),
-        3 => validate_three_params(function, &as_strings).map(|t| t.to_owned()),
+const fn compute(name: &'static str, since: u32, overloads: &'static [Overload]) -> Builtin {
+    Builtin { name, overloads, stage: Some(ShaderType::Compute), since }
+}
 
-        n => Err(format!("Error: Function '{}' does not accept {} parameters", function, n)),
-    }
+// Overloads shared by many functions
+const GEN_1: &[Overload] = &[(GEN, &[GEN])];
+const GEN_2: &[Overload] = &[(GEN, &[GEN, GEN])];
+const GEN_FD_1: &[Overload] = &[(GEN, &[GEN]), (GEN_D, &[GEN_D])];
+const MIN_MAX: &[Overload] = &[
+    (GEN, &[GEN, GEN]), (GEN, &[GEN, FLOAT]),
+    (GEN_D, &[GEN_D, GEN_D]), (GEN_D, &[GEN_D, DOUBLE]),
+    (GEN_I, &[GEN_I, GEN_I]), (GEN_I, &[GEN_I, INT]),
+    (GEN_U, &[GEN_U, GEN_U]), (GEN_U, &[GEN_U, UINT]),
+];
+const RELATIONAL: &[Overload] = &[(BVEC, &[VEC, VEC]), (BVEC, &[IVEC, IVEC]), (BVEC, &[UVEC, UVEC]), (BVEC, &[DVEC, DVEC])];
+const EQUALITY: &[Overload] = &[
+    (BVEC, &[VEC, VEC]), (BVEC, &[IVEC, IVEC]), (BVEC, &[UVEC, UVEC]), (BVEC, &[DVEC, DVEC]), (BVEC, &[BVEC, BVEC]),
+];
+const BARRIER: &[Overload] = &[(Void, &[])];
+
+const BUILTINS: &[Builtin] = &[
+    // Angle and trigonometry
+    builtin("radians", 110, GEN_1),
+    builtin("degrees", 110, GEN_1),
+    builtin("sin", 110, GEN_1),
+    builtin("cos", 110, GEN_1),
+    builtin("tan", 110, GEN_1),
+    builtin("asin", 110, GEN_1),
+    builtin("acos", 110, GEN_1),
+    builtin("atan", 110, &[(GEN, &[GEN, GEN]), (GEN, &[GEN])]),
+    builtin("sinh", 130, GEN_1),
+    builtin("cosh", 130, GEN_1),
+    builtin("tanh", 130, GEN_1),
+    builtin("asinh", 130, GEN_1),
+    builtin("acosh", 130, GEN_1),
+    builtin("atanh", 130, GEN_1),
+
+    // Exponential
+    builtin("pow", 110, GEN_2),
+    builtin("exp", 110, GEN_1),
+    builtin("log", 110, GEN_1),
+    builtin("exp2", 110, GEN_1),
+    builtin("log2", 110, GEN_1),
+    builtin("sqrt", 110, GEN_FD_1),
+    builtin("inversesqrt", 110, GEN_FD_1),
+
+    // Common
+    builtin("abs", 110, GEN_FD_1),
+    builtin("abs", 130, &[(GEN_I, &[GEN_I])]),
+    builtin("sign", 110, GEN_FD_1),
+    builtin("sign", 130, &[(GEN_I, &[GEN_I])]),
+    builtin("floor", 110, GEN_FD_1),
+    builtin("trunc", 130, GEN_FD_1),
+    builtin("round", 130, GEN_FD_1),
+    builtin("roundEven", 130, GEN_FD_1),
+    builtin("ceil", 110, GEN_FD_1),
+    builtin("fract", 110, GEN_FD_1),
+    builtin("mod", 110, &[(GEN, &[GEN, GEN]), (GEN, &[GEN, FLOAT]), (GEN_D, &[GEN_D, GEN_D]), (GEN_D, &[GEN_D, DOUBLE])]),
+    builtin("modf", 130, &[(GEN, &[GEN, Out(&GEN)]), (GEN_D, &[GEN_D, Out(&GEN_D)])]),
+    builtin("min", 110, MIN_MAX),
+    builtin("max", 110, MIN_MAX),
+    builtin("clamp", 110, &[
+        (GEN, &[GEN, GEN, GEN]), (GEN, &[GEN, FLOAT, FLOAT]),
+        (GEN_D, &[GEN_D, GEN_D, GEN_D]), (GEN_D, &[GEN_D, DOUBLE, DOUBLE]),
+        (GEN_I, &[GEN_I, GEN_I, GEN_I]), (GEN_I, &[GEN_I, INT, INT]),
+        (GEN_U, &[GEN_U, GEN_U, GEN_U]), (GEN_U, &[GEN_U, UINT, UINT]),
+    ]),
+    builtin("mix", 110, &[
+        (GEN, &[GEN, GEN, GEN]), (GEN, &[GEN, GEN, FLOAT]),
+        (GEN_D, &[GEN_D, GEN_D, GEN_D]), (GEN_D, &[GEN_D, GEN_D, DOUBLE]),
+    ]),
+    // Selecting components
+    builtin("mix", 130, &[(GEN, &[GEN, GEN, GEN_B]), (GEN_D, &[GEN_D, GEN_D, GEN_B])]),
+    // Selecting integers and booleans
+    builtin("mix", 450, &[(GEN_I, &[GEN_I, GEN_I, GEN_B]), (GEN_U, &[GEN_U, GEN_U, GEN_B]), (GEN_B, &[GEN_B, GEN_B, GEN_B])]),
+    builtin("step", 110, &[(GEN, &[GEN, GEN]), (GEN, &[FLOAT, GEN]), (GEN_D, &[GEN_D, GEN_D]), (GEN_D, &[DOUBLE, GEN_D])]),
+    builtin("smoothstep", 110, &[
+        (GEN, &[GEN, GEN, GEN]), (GEN, &[FLOAT, FLOAT, GEN]),
+        (GEN_D, &[GEN_D, GEN_D, GEN_D]), (GEN_D, &[DOUBLE, DOUBLE, GEN_D]),
+    ]),
+    builtin("isnan", 130, &[(GEN_B, &[GEN]), (GEN_B, &[GEN_D])]),
+    builtin("isinf", 130, &[(GEN_B, &[GEN]), (GEN_B, &[GEN_D])]),
+    builtin("floatBitsToInt", 330, &[(GEN_I, &[GEN])]),
+    builtin("floatBitsToUint", 330, &[(GEN_U, &[GEN])]),
+    builtin("intBitsToFloat", 330, &[(GEN, &[GEN_I])]),
+    builtin("uintBitsToFloat", 330, &[(GEN, &[GEN_U])]),
+    builtin("fma", 400, &[(GEN, &[GEN, GEN, GEN]), (GEN_D, &[GEN_D, GEN_D, GEN_D])]),
+    builtin("frexp", 400, &[(GEN, &[GEN, Out(&GEN_I)]), (GEN_D, &[GEN_D, Out(&GEN_I)])]),
+    builtin("ldexp", 400, &[(GEN, &[GEN, GEN_I]), (GEN_D, &[GEN_D, GEN_I])]),
+
+    // Floating-point pack and unpack
+    builtin("packUnorm2x16", 400, &[(UINT, &[VEC2])]),
+    builtin("packSnorm2x16", 420, &[(UINT, &[VEC2])]),
+    builtin("packUnorm4x8", 400, &[(UINT, &[VEC4])]),
+    builtin("packSnorm4x8", 400, &[(UINT, &[VEC4])]),
+    builtin("unpackUnorm2x16", 400, &[(VEC2, &[UINT])]),
+    builtin("unpackSnorm2x16", 420, &[(VEC2, &[UINT])]),
+    builtin("unpackUnorm4x8", 400, &[(VEC4, &[UINT])]),
+    builtin("unpackSnorm4x8", 400, &[(VEC4, &[UINT])]),
+    builtin("packHalf2x16", 420, &[(UINT, &[VEC2])]),
+    builtin("unpackHalf2x16", 420, &[(VEC2, &[UINT])]),
+    builtin("packDouble2x32", 400, &[(DOUBLE, &[UVEC2])]),
+    builtin("unpackDouble2x32", 400, &[(UVEC2, &[DOUBLE])]),
+
+    // Geometric
+    builtin("length", 110, &[(FLOAT, &[GEN]), (DOUBLE, &[GEN_D])]),
+    builtin("distance", 110, &[(FLOAT, &[GEN, GEN]), (DOUBLE, &[GEN_D, GEN_D])]),
+    builtin("dot", 110, &[(FLOAT, &[GEN, GEN]), (DOUBLE, &[GEN_D, GEN_D])]),
+    builtin("cross", 110, &[(VEC3, &[VEC3, VEC3]), (DVEC3, &[DVEC3, DVEC3])]),
+    builtin("normalize", 110, GEN_FD_1),
+    builtin("faceforward", 110, &[(GEN, &[GEN, GEN, GEN]), (GEN_D, &[GEN_D, GEN_D, GEN_D])]),
+    builtin("reflect", 110, &[(GEN, &[GEN, GEN]), (GEN_D, &[GEN_D, GEN_D])]),
+    builtin("refract", 110, &[(GEN, &[GEN, GEN, FLOAT]), (GEN_D, &[GEN_D, GEN_D, DOUBLE])]),
+
+    // Matrix
+    builtin("matrixCompMult", 110, &[(MAT, &[MAT, MAT]), (DMAT, &[DMAT, DMAT])]),
+    builtin("outerProduct", 120, &[
+        (MAT, &[Column(Scalar::Float), Row(Scalar::Float)]),
+        (DMAT, &[Column(Scalar::Double), Row(Scalar::Double)]),
+    ]),
+    builtin("transpose", 120, &[(Transposed(Scalar::Float), &[MAT]), (Transposed(Scalar::Double), &[DMAT])]),
+    builtin("determinant", 150, &[(FLOAT, &[SquareMat(Scalar::Float)]), (DOUBLE, &[SquareMat(Scalar::Double)])]),
+    builtin("inverse", 140, &[
+        (SquareMat(Scalar::Float), &[SquareMat(Scalar::Float)]),
+        (SquareMat(Scalar::Double), &[SquareMat(Scalar::Double)]),
+    ]),
+
+    // Vector relational
+    builtin("lessThan", 110, RELATIONAL),
+    builtin("lessThanEqual", 110, RELATIONAL),
+    builtin("greaterThan", 110, RELATIONAL),
+    builtin("greaterThanEqual", 110, RELATIONAL),
+    builtin("equal", 110, EQUALITY),
+    builtin("notEqual", 110, EQUALITY),
+    builtin("any", 110, &[(BOOL, &[BVEC])]),
+    builtin("all", 110, &[(BOOL, &[BVEC])]),
+    builtin("not", 110, &[(BVEC, &[BVEC])]),
+
+    // Integer
+    builtin("uaddCarry", 400, &[(GEN_U, &[GEN_U, GEN_U, Out(&GEN_U)])]),
+    builtin("usubBorrow", 400, &[(GEN_U, &[GEN_U, GEN_U, Out(&GEN_U)])]),
+    builtin("umulExtended", 400, &[(Void, &[GEN_U, GEN_U, Out(&GEN_U), Out(&GEN_U)])]),
+    builtin("imulExtended", 400, &[(Void, &[GEN_I, GEN_I, Out(&GEN_I), Out(&GEN_I)])]),
+    builtin("bitfieldExtract", 400, &[(GEN_I, &[GEN_I, INT, INT]), (GEN_U, &[GEN_U, INT, INT])]),
+    builtin("bitfieldInsert", 400, &[(GEN_I, &[GEN_I, GEN_I, INT, INT]), (GEN_U, &[GEN_U, GEN_U, INT, INT])]),
+    builtin("bitfieldReverse", 400, &[(GEN_I, &[GEN_I]), (GEN_U, &[GEN_U])]),
+    builtin("bitCount", 400, &[(GEN_I, &[GEN_I]), (GEN_I, &[GEN_U])]),
+    builtin("findLSB", 400, &[(GEN_I, &[GEN_I]), (GEN_I, &[GEN_U])]),
+    builtin("findMSB", 400, &[(GEN_I, &[GEN_I]), (GEN_I, &[GEN_U])]),
+
+    // Texture queries
+    builtin("textureSize", 130, &[
+        (IVEC2, &[SAMPLER_2D, INT]), (IVEC3, &[SAMPLER_3D, INT]),
+        (IVEC2, &[SAMPLER_CUBE, INT]), (IVEC3, &[SAMPLER_2D_ARRAY, INT]),
+    ]),
+    fragment("textureQueryLod", 400, &[
+        (VEC2, &[SAMPLER_2D, VEC2]), (VEC2, &[SAMPLER_3D, VEC3]),
+        (VEC2, &[SAMPLER_CUBE, VEC3]), (VEC2, &[SAMPLER_2D_ARRAY, VEC2]),
+    ]),
+    builtin("textureQueryLevels", 430, &[(INT, &[SAMPLER_2D]), (INT, &[SAMPLER_3D]), (INT, &[SAMPLER_CUBE]), (INT, &[SAMPLER_2D_ARRAY])]),
+
+    // Texel lookup
+    builtin("texture", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC2]), (VEC4, &[SAMPLER_3D, VEC3]),
+        (VEC4, &[SAMPLER_CUBE, VEC3]), (VEC4, &[SAMPLER_2D_ARRAY, VEC3]),
+    ]),
+    // Only fragment shaders compute implicit derivatives, so only they accept a bias
+    fragment("texture", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC2, FLOAT]), (VEC4, &[SAMPLER_3D, VEC3, FLOAT]),
+        (VEC4, &[SAMPLER_CUBE, VEC3, FLOAT]), (VEC4, &[SAMPLER_2D_ARRAY, VEC3, FLOAT]),
+    ]),
+    builtin("textureProj", 130, &[(VEC4, &[SAMPLER_2D, VEC3]), (VEC4, &[SAMPLER_2D, VEC4]), (VEC4, &[SAMPLER_3D, VEC4])]),
+    builtin("textureProjOffset", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC3, IVEC2]), (VEC4, &[SAMPLER_2D, VEC4, IVEC2]), (VEC4, &[SAMPLER_3D, VEC4, IVEC3]),
+    ]),
+    builtin("textureProjLod", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC3, FLOAT]), (VEC4, &[SAMPLER_2D, VEC4, FLOAT]), (VEC4, &[SAMPLER_3D, VEC4, FLOAT]),
+    ]),
+    builtin("textureProjLodOffset", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC3, FLOAT, IVEC2]), (VEC4, &[SAMPLER_2D, VEC4, FLOAT, IVEC2]),
+        (VEC4, &[SAMPLER_3D, VEC4, FLOAT, IVEC3]),
+    ]),
+    builtin("textureProjGrad", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC3, VEC2, VEC2]), (VEC4, &[SAMPLER_2D, VEC4, VEC2, VEC2]),
+        (VEC4, &[SAMPLER_3D, VEC4, VEC3, VEC3]),
+    ]),
+    builtin("textureProjGradOffset", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC3, VEC2, VEC2, IVEC2]), (VEC4, &[SAMPLER_2D, VEC4, VEC2, VEC2, IVEC2]),
+        (VEC4, &[SAMPLER_3D, VEC4, VEC3, VEC3, IVEC3]),
+    ]),
+    builtin("textureLod", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC2, FLOAT]), (VEC4, &[SAMPLER_3D, VEC3, FLOAT]),
+        (VEC4, &[SAMPLER_CUBE, VEC3, FLOAT]), (VEC4, &[SAMPLER_2D_ARRAY, VEC3, FLOAT]),
+    ]),
+    builtin("textureOffset", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC2, IVEC2]), (VEC4, &[SAMPLER_3D, VEC3, IVEC3]), (VEC4, &[SAMPLER_2D_ARRAY, VEC3, IVEC2]),
+    ]),
+    builtin("texelFetch", 130, &[
+        (VEC4, &[SAMPLER_2D, IVEC2, INT]), (VEC4, &[SAMPLER_3D, IVEC3, INT]), (VEC4, &[SAMPLER_2D_ARRAY, IVEC3, INT]),
+    ]),
+    builtin("texelFetchOffset", 130, &[
+        (VEC4, &[SAMPLER_2D, IVEC2, INT, IVEC2]), (VEC4, &[SAMPLER_3D, IVEC3, INT, IVEC3]),
+        (VEC4, &[SAMPLER_2D_ARRAY, IVEC3, INT, IVEC2]),
+    ]),
+    builtin("textureLodOffset", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC2, FLOAT, IVEC2]), (VEC4, &[SAMPLER_3D, VEC3, FLOAT, IVEC3]),
+        (VEC4, &[SAMPLER_2D_ARRAY, VEC3, FLOAT, IVEC2]),
+    ]),
+    builtin("textureGrad", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC2, VEC2, VEC2]), (VEC4, &[SAMPLER_3D, VEC3, VEC3, VEC3]),
+        (VEC4, &[SAMPLER_CUBE, VEC3, VEC3, VEC3]), (VEC4, &[SAMPLER_2D_ARRAY, VEC3, VEC2, VEC2]),
+    ]),
+    builtin("textureGradOffset", 130, &[
+        (VEC4, &[SAMPLER_2D, VEC2, VEC2, VEC2, IVEC2]), (VEC4, &[SAMPLER_3D, VEC3, VEC3, VEC3, IVEC3]),
+        (VEC4, &[SAMPLER_2D_ARRAY, VEC3, VEC2, VEC2, IVEC2]),
+    ]),
+    builtin("textureGather", 400, &[
+        (VEC4, &[SAMPLER_2D, VEC2]), (VEC4, &[SAMPLER_2D, VEC2, INT]),
+        (VEC4, &[SAMPLER_CUBE, VEC3]), (VEC4, &[SAMPLER_CUBE, VEC3, INT]),
+        (VEC4, &[SAMPLER_2D_ARRAY, VEC3]), (VEC4, &[SAMPLER_2D_ARRAY, VEC3, INT]),
+    ]),
+    builtin("textureGatherOffset", 400, &[
+        (VEC4, &[SAMPLER_2D, VEC2, IVEC2]), (VEC4, &[SAMPLER_2D, VEC2, IVEC2, INT]),
+        (VEC4, &[SAMPLER_2D_ARRAY, VEC3, IVEC2]), (VEC4, &[SAMPLER_2D_ARRAY, VEC3, IVEC2, INT]),
+    ]),
+
+    // Fragment processing
+    fragment("dFdx", 110, GEN_1),
+    fragment("dFdy", 110, GEN_1),
+    fragment("fwidth", 110, GEN_1),
+    fragment("dFdxFine", 450, GEN_1),
+    fragment("dFdyFine", 450, GEN_1),
+    fragment("fwidthFine", 450, GEN_1),
+    fragment("dFdxCoarse", 450, GEN_1),
+    fragment("dFdyCoarse", 450, GEN_1),
+    fragment("fwidthCoarse", 450, GEN_1),
+    fragment("interpolateAtCentroid", 400, GEN_1),
+    fragment("interpolateAtSample", 400, &[(GEN, &[GEN, INT])]),
+    fragment("interpolateAtOffset", 400, &[(GEN, &[GEN, VEC2])]),
+
+    // Noise (deprecated, always returns zero)
+    builtin("noise1", 110, &[(FLOAT, &[GEN])]),
+    builtin("noise2", 110, &[(VEC2, &[GEN])]),
+    builtin("noise3", 110, &[(VEC3, &[GEN])]),
+    builtin("noise4", 110, &[(VEC4, &[GEN])]),
+
+    // Shader invocation control and memory control
+    compute("barrier", 430, BARRIER),
+    builtin("memoryBarrier", 420, BARRIER),
+    builtin("memoryBarrierAtomicCounter", 420, BARRIER),
+    builtin("memoryBarrierBuffer", 430, BARRIER),
+    builtin("memoryBarrierImage", 430, BARRIER),
+    compute("memoryBarrierShared", 430, BARRIER),
+    compute("groupMemoryBarrier", 430, BARRIER),
+
+    // Shader invocation group
+    builtin("anyInvocation", 460, &[(BOOL, &[BOOL])]),
+    builtin("allInvocations", 460, &[(BOOL, &[BOOL])]),
+    builtin("allInvocationsEqual", 460, &[(BOOL, &[BOOL])]),
+];
+
+/// Functions that were removed from core GLSL, with their replacement
+const REMOVED: &[(&str, &str)] = &[
+    ("texture2D", "texture"),
+    ("texture3D", "texture"),
+    ("textureCube", "texture"),
+    ("texture2DLod", "textureLod"),
+    ("shadow2D", "texture"),
+];
+
+pub fn is_builtin(function: &str) -> bool {
+    BUILTINS.iter().any(|builtin| builtin.name == function) || REMOVED.iter().any(|(name, _)| *name == function)
 }
 
-pub fn can_arrow(function: &str, types: &Vec<TypeSpecifier>) -> Result<String, String> {
-    if function != "min" && function != "max" {
-        return Err(format!("Function '{}' cannot use '<-' syntax", function));
-    } else {
-        let expected_type = types[0].as_string();
-        for ty in types {
-            if ty.as_string() != expected_type {
-                return Err(format!("To use '<-' syntax with '{}', all types must be same (got {:?})", function, types));
-            }
+/// Whether the argument at `index` is an `out` parameter of the builtin (like the second of `modf`)
+pub fn writes_argument(function: &str, index: usize) -> bool {
+    BUILTINS.iter()
+        .filter(|builtin| builtin.name == function)
+        .flat_map(|builtin| builtin.overloads.iter())
+        .any(|(_, params)| matches!(params.get(index), Some(Out(_))))
+}
+
+impl std::fmt::Display for Param {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = |scalar: &Scalar| scalar.vector_prefix();
+
+        match self {
+            Gen(scalar) => write!(f, "gen{}Type", prefix(scalar).to_uppercase()),
+            GenVec(scalar) => write!(f, "{}vec", prefix(scalar)),
+            Param::Scalar(scalar) => write!(f, "{}", scalar.name()),
+            Vector(scalar, size) => write!(f, "{}", Type::Vector(*scalar, *size)),
+            Mat(scalar) | Transposed(scalar) => write!(f, "{}mat", prefix(scalar)),
+            SquareMat(scalar) => write!(f, "{}matN", prefix(scalar)),
+            Column(scalar) | Row(scalar) => write!(f, "{}vec", prefix(scalar)),
+            Param::Sampler(sampler) => write!(f, "{}", sampler.name()),
+            Out(param) => write!(f, "out {}", param),
+            Void => write!(f, "void"),
         }
     }
+}
 
-    // This will never allow arrays because no GLSL functions work with arrays
-    let strings = types.iter().map(|t| t.as_string()).collect();
-    validate_two_params(function, &strings).map(|t| t.to_owned())
+/// Sizes that generic parameters have been bound to while matching an overload
+#[derive(Default)]
+struct Bindings {
+    size: Option<usize>,
+    columns: Option<usize>,
+    rows: Option<usize>,
 }
 
-// TODO: Matrices:  matrixCompMult,
-//       Bool Vecs: lessThan, lessThanEqual, greaterThan, greaterThanEqual, equal, notEqual, any, all, not
-
-fn validate_three_params(function: &str, types: &Vec<String>) -> Result<String, String> {
-    match function {
-        // return_type = function(return_type, return_type or float, return_type or float)
-        "clamp" => {
-            // FIXME: All four cases aren't need here
-            if (  ( types[0] == types[1]) && (types[1] == types[2]) )
-               || ( types[1] == "float"   && types[2] == types[0]   )
-               || ( types[1] == "float"   && types[2] == "float"    )  
-               || ( types[1] == "float"   && types[2] == types[0]   )
-            {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[0].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[0], types)),
-                }
-            } else {
-                Err(format!("Error: '{}' requires all three parameters to be same type unless the second and third are floats", function))
-            }
-        }
+fn bind(slot: &mut Option<usize>, value: usize) -> bool {
+    *slot.get_or_insert(value) == value
+}
 
-        // return_type = function(return_type, return_type, return_type or float)
-        "mix" => {
-            if (types[0] == types[1]) && ((types[1] == types[2]) || types[2] == "float") {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[0].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[2], types)),
-                }
-            } else {
-                Err(format!("Error: '{}' requires all three parameters to be same type unless the third is a float (got {:?})", function, types))
-            }
-        }
+/// Whether an argument can be passed as a parameter, binding the sizes of generic parameters.
+/// `exact` disallows implicit conversions
+fn accepts(param: &Param, argument: &Type, bindings: &mut Bindings, exact: bool) -> bool {
+    let converts = |from: &Scalar, to: &Scalar| from == to || (!exact && scalar_castable(*from, *to));
 
-        // return_type = function(return_type or float, return_type or float, return_type)
-        "smoothstep" => {
-            if ((types[0] == types[1]) && (types[1] == types[2]))
-               || ( types[0] == "float" && types[1] == "float" )  
-            {
-                match types[2].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[2].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[2], types)),
-                }
-            } else {
-                Err(format!("Error: '{}' requires all three parameters to be same type unless the first and second are floats", function))
-            }
+    match (param, argument) {
+        (Gen(to), Type::Scalar(from)) => converts(from, to) && bind(&mut bindings.size, 1),
+        (Gen(to), Type::Vector(from, size)) | (GenVec(to), Type::Vector(from, size)) => {
+            converts(from, to) && bind(&mut bindings.size, *size)
         }
 
-        // return_type = function(return_type, return_type, return_type)
-        "faceforward" => {
-            if (types[0] == types[1]) && (types[1] == types[2]) {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[0].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[0], types)),
-                }
-            } else {
-                Err(format!("Error: '{}' requires all three parameters to be same type", function))
-            }
-        }
+        (Param::Scalar(to), Type::Scalar(from)) => converts(from, to),
+        (Vector(to, expected), Type::Vector(from, size)) => converts(from, to) && expected == size,
 
-        // return_type = function(return_type, return_type, float)
-        "refract" => {
-            if (types[0] == types[1]) && (types[2] == "float") {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[0].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[0], types)),
-                }
-            } else {
-                Err(format!("Error: '{}' requires two of the same types and a float for the third parameter", function))
-            }
+        (Mat(to), Type::Matrix(shape)) => {
+            converts(&shape.primitive(), to) && bind(&mut bindings.columns, shape.columns) && bind(&mut bindings.rows, shape.rows)
         }
-
-        "texture2D" => {
-            if (types[0] == "sampler2D") && (types[1] == "vec2") && (types[2] == "float") {
-                Ok("vec4".to_owned())
-            } else {
-                Err(format!("Error: '{}' with three parameters requires 'sampler2D', 'vec2', and 'float'. Found '{:?}'", function, types))
-            }
+        (SquareMat(to), Type::Matrix(shape)) => {
+            converts(&shape.primitive(), to) && shape.is_square()
+                && bind(&mut bindings.columns, shape.columns) && bind(&mut bindings.rows, shape.rows)
         }
 
-        "textureCube" => {
-            if (types[0] == "samplerCube") && (types[1] == "vec2") && (types[2] == "float") {
-                Ok("vec4".to_owned())
-            } else {
-                Err(format!("Error: '{}' with three parameters requires 'samplerCube', 'vec2', and 'float'. Found '{:?}'", function, types))
-            }
-        }
+        (Column(to), Type::Vector(from, size)) => converts(from, to) && bind(&mut bindings.rows, *size),
+        (Row(to), Type::Vector(from, size)) => converts(from, to) && bind(&mut bindings.columns, *size),
+
+        (Param::Sampler(sampler), Type::Sampler(passed)) => sampler == passed,
 
-        _ => Err(format!("Error: '{}' does not accept three parameters", function)),
+        (Out(param), _) => accepts(param, argument, bindings, true),
+
+        _ => false,
     }
 }
 
-fn validate_two_params(function: &str, types: &Vec<String>) -> Result<String, String> {
-    match function {
-        // vec4 = function(sampler2D, vec2)
-        "texture2D" => {
-            if types[0] == "sampler2D" && types[1] == "vec2" {
-                Ok("vec4".to_owned())
-            } else {
-                Err(format!("Error: '{}' with two parameters requires 'sampler2D' and 'vec2'. Found '{:?}'", function, types))
-            }
-        }
+/// The return type of an overload whose parameters were matched
+fn resolve(param: &Param, bindings: &Bindings) -> Type {
+    let matrix = |scalar: &Scalar, columns, rows| Type::Matrix(MatShape { double: *scalar == Scalar::Double, columns, rows });
+
+    match param {
+        Gen(scalar) => Type::vector(*scalar, bindings.size.unwrap()),
+        GenVec(scalar) => Type::Vector(*scalar, bindings.size.unwrap()),
+        Param::Scalar(scalar) => Type::Scalar(*scalar),
+        Vector(scalar, size) => Type::Vector(*scalar, *size),
+        Mat(scalar) | SquareMat(scalar) => matrix(scalar, bindings.columns.unwrap(), bindings.rows.unwrap()),
+        Transposed(scalar) => matrix(scalar, bindings.rows.unwrap(), bindings.columns.unwrap()),
+        Column(scalar) => Type::Vector(*scalar, bindings.rows.unwrap()),
+        Row(scalar) => Type::Vector(*scalar, bindings.columns.unwrap()),
+        Param::Sampler(sampler) => Type::Sampler(*sampler),
+        Out(param) => resolve(param, bindings),
+        Void => Type::Void,
+    }
+}
 
-        // vec4 = function(samplerCube, vec3)
-        "textureCube" => {
-            if types[0] == "samplerCube" && types[1] == "vec3" {
-                Ok("vec4".to_owned())
-            } else {
-                Err(format!("Error: '{}' with two parameters requires 'samplerCube' and 'vec3'. Found '{:?}'", function, types))
-            }
-        }
+fn overload_string(name: &str, (ret, params): &Overload) -> String {
+    let params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+    format!("{} {}({})", ret, name, params.join(", "))
+}
 
-        // vec3 = function(vec3, vec3)
-        "cross" => {
-            if types[0] == "vec3" && types[1] == "vec3" {
-                Ok("vec3".to_owned())
-            } else {
-                Err(format!("Error: '{}' accepts two of 'vec3'. Got {:?}", function, types))
-            }
-        }
+fn stage_name(stage: &ShaderType) -> &'static str {
+    match stage {
+        ShaderType::Vertex => "vertex",
+        ShaderType::Fragment => "fragment",
+        ShaderType::Compute => "compute",
+        ShaderType::Library => "library",
+    }
+}
 
-        // return_type = function(return_type, return_type)
-        "atan" | "pow" | "reflect" => {
-            if types[0] == types[1] {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[0].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[0], types)),
-                }
-            } else {
-                Err(format!("Error: '{}' requires two of the same types", function))
-            }
-        }
+/// Finds the overload of a builtin matching the passed types, returning its type.
+/// Exact matches are preferred over ones that need implicit conversions (GLSL's overload resolution)
+pub fn validate_function(function: &str, types: &[Type], stage: &ShaderType, version: u32) -> Result<Type, String> {
+    if let Some((_, replacement)) = REMOVED.iter().find(|(name, _)| *name == function) {
+        return Err(format!("Error: '{}' is not available in core GLSL (use '{}' instead)", function, replacement));
+    }
+
+    let candidates: Vec<&Builtin> = BUILTINS.iter().filter(|builtin| builtin.name == function).collect();
+    // Reported only if no other overload is available, since a later one may not be gated
+    let mut gated = None;
+
+    for exact in &[true, false] {
+        for builtin in &candidates {
+            for overload in builtin.overloads {
+                let mut bindings = Bindings::default();
+                let (ret, params) = overload;
 
-        // float = function(type, type)
-        "distance" | "dot" => {
-            if types[0] == types[1] {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok("float".to_owned()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[0], types)),
+                if params.len() != types.len() || !params.iter().zip(types).all(|(p, t)| accepts(p, t, &mut bindings, *exact)) {
+                    continue;
                 }
-            } else {
-                Err(format!("Error: '{}' requires two of the same types", function))
-            }
-        }
 
-        // return_type = function(return_type, return_type or float)
-        "mod" | "min" | "max" => {
-            if (types[0] == types[1]) || types[1] == "float" {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[0].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[0], types)),
+                // Libraries may be imported by any stage
+                match &builtin.stage {
+                    Some(required) if required != stage && *stage != ShaderType::Library => {
+                        gated.get_or_insert_with(|| format!("Error: '{}' is only available in {} shaders", overload_string(function, overload), stage_name(required)));
+                        continue;
+                    }
+                    _ => {}
                 }
-            } else {
-                Err(format!("Error: '{}' requires two of the same type unless the second parameter is a float", function))
-            }
-        }
 
-        // return_type = function(return_type or float, return_type)
-        "step" => {
-            if (types[0] == types[1]) || types[0] == "float" {
-                match types[0].as_ref() {
-                    "float" | "vec2" | "vec3" | "vec4" => Ok(types[0].clone()),
-                    _ => Err(format!("Error: '{}' does not accept type '{}' (got {:?})", function, &types[0], types)),
+                if builtin.since > version {
+                    gated.get_or_insert_with(|| format!("Error: '{}' requires GLSL {} (compiling for GLSL {}, see '--glsl-version')",
+                                                        overload_string(function, overload), builtin.since, version));
+                    continue;
                 }
-            } else {
-                Err(format!("Error: '{}' requires two of the same types unless the first parameter is a float", function))
+
+                return Ok(resolve(ret, &bindings));
             }
         }
-
-        _ => Err(format!("Error: '{}' does not accept two parameters", function)),
     }
-}
 
-fn validate_single_param<'a>(function: &str, ty: &'a str) -> Result<&'a str, String> {
-    match function {
-        "radians" | "degrees"     | 
-        "sin"     | "cos"         | "tan"   |
-        "asin"    | "acos"        | "atan"  |
-        "exp"     | "log"         | "exp2"  | "log2" |
-        "sqrt"    | "inversesqrt" |
-        "abs"     | "sign"        | 
-        "floor"   | "ceil"        | "fract" |
-        "normalize"
-        => {
-            // This is ok here (see top todo)
-            if castable(ty, "float")? {
-                return Ok("float");
-            }
+    if let Some(error) = gated {
+        return Err(error);
+    }
 
-            match ty {
-                "float" | "vec2" | "vec3" | "vec4" => Ok(ty),
-                _ => Err(format!("Error: '{}' does not work with type '{}'", function, ty)),
-            }
-        }
+    let overloads: Vec<String> = candidates.iter()
+        .flat_map(|builtin| builtin.overloads.iter().map(move |overload| format!("\n    {}", overload_string(builtin.name, overload))))
+        .collect();
 
-        "length" => {
-            if castable(ty, "float")? {
-                return Ok("float");
-            }
+    Err(format!("Error: No overload of '{}' accepts {}. The overloads are:{}", function, tuple_string(types), overloads.concat()))
+}
 
-            match ty {
-                "float" | "vec2" | "vec3" | "vec4" => Ok("float"),
-                _ => Err(format!("Error: '{}' does not work with type '{}'", function, ty)),
+pub fn can_arrow(function: &str, types: &[Type], stage: &ShaderType, version: u32) -> Result<Type, String> {
+    if function != "min" && function != "max" {
+        return Err(format!("Function '{}' cannot use '<-' syntax", function));
+    } else {
+        let expected_type = &types[0];
+        for ty in types {
+            if ty != expected_type {
+                return Err(format!("To use '<-' syntax with '{}', all types must be same (got {})", function, tuple_string(types)));
             }
         }
-
-        _ => Err(format!("Error: '{}' does not accept one parameter", function)),
     }
-}
\ No newline at end of file
+
+    // This will never allow arrays because no GLSL functions work with arrays
+    validate_function(function, &types[..2], stage, version)
+}
@@ -1,107 +1,124 @@
-// TODO: Arrays, then matrices
-// Note that a matrix is just a 2d array of vecs/dvecs
+use crate::parse::types::{Scalar, Type};
 
-// TODO: Just use a regex for this. Or implement a stack automata
-pub fn is_mat_constructor_or_type(mut name: &str) -> bool {
-    if name.len() > 0 && &name[0..=0] == "d" {
-        name = &name[1..];
-    }
+use super::castable;
 
-    if !(name.len() > 3 && &name[0..=3] == "mat") {
-        return false;
-    }
+// Note that a matrix is a set of column vectors. `matCxR` has C columns and R rows,
+// and `matN` is the same as `matNxN`.
+// See https://www.khronos.org/opengl/wiki/Data_Type_(GLSL)#Matrices
 
-    match name.len() {
-        // matN
-        4 => {
-            match &name[3..=3] {
-                "2" | "3" | "4" => true,
-                _ => false,
-            }
-        }
+/// Dimensions of a matrix type
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MatShape {
+    pub double: bool,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl MatShape {
+    /// The canonical name of the type (`mat3` rather than `mat3x3`)
+    pub fn name(&self) -> String {
+        let prefix = if self.double { "dmat" } else { "mat" };
 
-        // matNxM
-        6 => {
-            match &name[3..=3] {
-                "2" | "3" | "4" => {
-                    if &name[4..=4] == "x" {
-                        match &name[5..=5] {
-                            "2" | "3" | "4" => true,
-                            _ => false,
-                        }
-                    } else {
-                        false
-                    }
-                },
-                _ => false,
-            }
+        if self.columns == self.rows {
+            format!("{}{}", prefix, self.columns)
+        } else {
+            format!("{}{}x{}", prefix, self.columns, self.rows)
         }
+    }
 
-        _ => false,
+    /// Type of a single element
+    pub fn primitive(&self) -> Scalar {
+        if self.double { Scalar::Double } else { Scalar::Float }
     }
-}
 
-struct MatInputCursor<'types> {
-    types: &'types Vec<String>,
-    current: usize,
-}
+    /// Type of a column (the result of `mat[i]`)
+    pub fn column_type(&self) -> Type {
+        Type::Vector(self.primitive(), self.rows)
+    }
 
-impl<'types> MatInputCursor<'types> {
-    fn new(types: &'types Vec<String>) -> Self {
-        MatInputCursor {
-            types,
-            current: 0,
-        }
+    /// Type of a row
+    pub fn row_type(&self) -> Type {
+        Type::Vector(self.primitive(), self.columns)
     }
 
-    fn next_n(&mut self, n: usize) -> Vec<String> {
-        let mut amount = 0;
-        while amount < n {
-            match self.types[self.current + amount].as_ref() {
-                "vec2" | "dvec2" => {
-                    amount += 2;
-                }
-
-                "int" | "float" | "double" => {
-                    amount += 1;
-                }
-
-                _ => {
-                    // TODO: Error
-                }
-            }
-        }
-        
-        self.current += n;
+    pub fn is_square(&self) -> bool {
+        self.columns == self.rows
+    }
 
-        // TODO: Is this correct?
-        self.types[(self.current - n) ..= self.current].to_vec()
+    pub fn transposed(&self) -> MatShape {
+        MatShape {
+            double: self.double,
+            columns: self.rows,
+            rows: self.columns,
+        }
     }
 }
 
-/// Returns mat type if the constructor is valid
-pub fn validate_constructor(mut mat_type: &str, passed: &Vec<String>) -> Result<String, String> {
-    let primitive = if &mat_type[0..=0] == "d" {
-        "double"
+/// Parses `matN`, `matNxM`, `dmatN`, or `dmatNxM`
+pub fn shape(name: &str) -> Option<MatShape> {
+    let (double, dimensions) = if let Some(rest) = name.strip_prefix("dmat") {
+        (true, rest)
+    } else if let Some(rest) = name.strip_prefix("mat") {
+        (false, rest)
     } else {
-        "float"
+        return None;
+    };
+
+    let dimension = |d: &str| match d {
+        "2" => Some(2),
+        "3" => Some(3),
+        "4" => Some(4),
+        _ => None,
+    };
+
+    let (columns, rows) = match dimensions.len() {
+        1 => (dimension(dimensions)?, dimension(dimensions)?),
+        3 if &dimensions[1..2] == "x" => (dimension(&dimensions[0..1])?, dimension(&dimensions[2..3])?),
+        _ => return None,
     };
 
-    let cursor = MatInputCursor::new(passed);
+    Some(MatShape { double, columns, rows })
+}
 
-    // Matrix rows can be built just like vectors
-    // can use vector validation to see if matrix rows are valid
-    // then, if all rows are valid, the matrix is valid
-    //
-    // Valid:  mat3 z = mat3(vec2(1.), 1., vec3(1.), vec3(1.));
+pub fn is_mat_constructor_or_type(name: &str) -> bool {
+    shape(name).is_some()
+}
 
-    match mat_type {
-        // "mat2" | "dmat2" | "mat2x2" | "dmat2x2" => {
-        //     if passed.len() != 
-        //      ...
-        //     vec::validate_constructor("vec2", cursor.next_n(2))
-        // }
+/// Returns mat type if the constructor is valid
+pub fn validate_constructor(shape: MatShape, passed: &[Type]) -> Result<Type, String> {
+    let result = Type::Matrix(shape);
+    let primitive = Type::Scalar(shape.primitive());
 
-        _ => Err(format!("Unknown matrix type, '{}'", mat_type))
+    if passed.is_empty() {
+        return Err(format!("Error: Type '{}' must be initialized with values", result));
+    }
+
+    if passed.len() == 1 {
+        // 'mat3(1.)' is a diagonal matrix
+        if castable(&passed[0], &primitive) {
+            return Ok(result);
+        }
+
+        // Any matrix can be built from another (extra values are dropped, missing values are from the identity)
+        if let Type::Matrix(_) = passed[0] {
+            return Ok(result);
+        }
     }
-}
\ No newline at end of file
+
+    // Otherwise components are consumed in column-major order
+    let mut components = 0;
+    for ty in passed {
+        match (ty.scalar(), ty.size()) {
+            (Some(scalar), Some(count)) if castable(&Type::Scalar(scalar), &primitive) => components += count,
+            _ => return Err(format!("Error: '{}' cannot be built from type '{}'", result, ty)),
+        }
+    }
+
+    let expected = shape.columns * shape.rows;
+    if components != expected {
+        return Err(format!("Error: '{}' requires {} components, but {} were given", result, expected, components));
+    }
+
+    Ok(result)
+}
+
@@ -1,102 +1,50 @@
 pub mod vec;
 pub mod mat;
 pub mod functions;
+pub mod operators;
+pub mod variables;
 
-// TODO: Implement vec casts like uvec to ivec, etc.
-
+use crate::parse::types::{Scalar, Type};
 
 /// Whether a narrowing conversion via 'as' is valid.
-pub fn narrow_castable(from: &str, to: &str) -> Result<bool, String> {
+pub fn narrow_castable(from: &Type, to: &Type) -> bool {
     if from == to {
-        return Ok(true);
+        return true;
     }
 
     // TODO: Can arrays of the same size cast between compatible base types??
-    if from.contains("[") || to.contains("[") {
-        return Ok(false);
-        // return Err(format!("Arrays cannot be cast (tried casting '{}' to '{}')", from, to));
-    }
-
-    match from {
-        "double" => {
-            match to {
-                "float" | "int" | "uint" => Ok(true),
-                _ => Ok(false),
-            }
-        }
-        
-        "float" => {
-            match to {
-                "double" | "int" | "uint" => Ok(true),
-                _ => Ok(false),
-            }
-        }
-
-        "int" => {
-            match to {
-                "float" | "double" | "uint" => Ok(true),
-                _ => Ok(false),
-            }
-        }
-
-        "uint" => {
-            match to {
-                "float" | "int" | "double" => Ok(true),
-                _ => Ok(false),
-            }
-        }
-
-        x => Err(format!("Type '{}' has no cast implementations. Cannot cast from '{}' to '{}'.", x, from, to)),
+    // TODO: Implement vec casts like uvec to ivec, etc.
+    match (from, to) {
+        (Type::Scalar(from), Type::Scalar(to)) => from.is_numeric() && to.is_numeric(),
+        _ => false,
     }
 }
 
 /// Whether types can be implicitly cast (non-narrowing cast)
-pub fn castable(from: &str, to: &str) -> Result<bool, String> {
+pub fn castable(from: &Type, to: &Type) -> bool {
     if from == to {
-        return Ok(true);
+        return true;
     }
 
-    // Cannot cast between array types (even for compatible base types)
-    if from.contains("[") || to.contains("[") {
-        return Ok(false);
-        // return Err(format!("Arrays cannot be cast (tried casting '{}' to '{}')", from, to));
-    }
+    match (from, to) {
+        (Type::Scalar(from), Type::Scalar(to)) => scalar_castable(*from, *to),
 
-    match to {
-        "double" => {
-            match from {
-                "float" | "int" | "uint" => Ok(true),
-                _ => Ok(false),
-            }
+        // `mat3` is `mat3x3`, and float matrices can be converted to double matrices
+        (Type::Matrix(from), Type::Matrix(to)) => {
+            from.columns == to.columns && from.rows == to.rows && (from.double == to.double || to.double)
         }
 
-        "float" => {
-            match from {
-                "int" | "uint" => Ok(true),
-                _ => Ok(false),
-            }
-        }
-
-        "int" => {
-            match from {
-                "uint" => Ok(true),
-                _ => Ok(false),
-            }
-        }
-
-        "uint" => {
-            match from {
-                _ => Ok(false),
-            }
-        }
-
-        "bool" => {
-            match from {
-                "double" | "fload" | "int" | "uint" => Ok(false),
-                _ => Ok(false),
-            }
-        }
+        // Cannot cast between array types (even for compatible base types)
+        _ => false,
+    }
+}
 
-        x => Err(format!("Type '{}' has no cast implementations. Cannot cast from '{}' to '{}'.", x, from, to)),
+/// Implicit conversions allowed by GLSL, `int` -> `uint` -> `float` -> `double`
+fn scalar_castable(from: Scalar, to: Scalar) -> bool {
+    match to {
+        Scalar::Double => matches!(from, Scalar::Float | Scalar::Int | Scalar::UInt),
+        Scalar::Float => matches!(from, Scalar::Int | Scalar::UInt),
+        Scalar::UInt => from == Scalar::Int,
+        Scalar::Int | Scalar::Bool => false,
     }
-}
\ No newline at end of file
+}
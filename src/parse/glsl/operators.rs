@@ -0,0 +1,190 @@
+use crate::parse::ast::BinaryOperator;
+use crate::parse::types::{Scalar, Type};
+
+use super::mat::MatShape;
+use super::scalar_castable;
+
+// See section 5.9 (Expressions) of the GLSL 4.50 specification
+// and https://www.khronos.org/opengl/wiki/Data_Type_(GLSL)#Implicit_conversion
+
+pub fn symbol(operator: &BinaryOperator) -> &'static str {
+    match operator {
+        BinaryOperator::Plus => "+",
+        BinaryOperator::Minus => "-",
+        BinaryOperator::Multiply => "*",
+        BinaryOperator::Divide => "/",
+        BinaryOperator::Cast => "as",
+        BinaryOperator::EqualTo => "==",
+        BinaryOperator::NotEqualTo => "!=",
+        BinaryOperator::GreaterThan => ">",
+        BinaryOperator::LessThan => "<",
+        BinaryOperator::GreaterThanOrEqualTo => ">=",
+        BinaryOperator::LessThanOrEqualTo => "<=",
+        BinaryOperator::And => "&&",
+        BinaryOperator::Or => "||",
+    }
+}
+
+/// Returns the type of `left operator right`. Casts are handled separately since their right side is a type name
+pub fn binary_type(operator: &BinaryOperator, left: &Type, right: &Type) -> Result<Type, String> {
+    match operator {
+        BinaryOperator::Plus | BinaryOperator::Minus | BinaryOperator::Multiply | BinaryOperator::Divide => {
+            arithmetic_type(operator, left, right)
+        }
+
+        BinaryOperator::GreaterThan | BinaryOperator::LessThan
+        | BinaryOperator::GreaterThanOrEqualTo | BinaryOperator::LessThanOrEqualTo => {
+            relational_type(operator, left, right)
+        }
+
+        BinaryOperator::EqualTo | BinaryOperator::NotEqualTo => equality_type(operator, left, right),
+
+        BinaryOperator::And | BinaryOperator::Or => {
+            if *left == Type::BOOL && *right == Type::BOOL {
+                Ok(Type::BOOL)
+            } else {
+                Err(format!("Operator '{}' requires 'bool' operands, but got '{}' and '{}'", symbol(operator), left, right))
+            }
+        }
+
+        BinaryOperator::Cast => unreachable!("casts are validated separately"),
+    }
+}
+
+/// The component type both sides are implicitly converted to (`int` -> `uint` -> `float` -> `double`)
+fn common_scalar(left: Scalar, right: Scalar) -> Option<Scalar> {
+    if left == right {
+        Some(left)
+    } else if scalar_castable(left, right) {
+        Some(right)
+    } else if scalar_castable(right, left) {
+        Some(left)
+    } else {
+        None
+    }
+}
+
+/// `+`, `-`, `*`, and `/`. Everything is component-wise except `*` with a matrix operand
+fn arithmetic_type(operator: &BinaryOperator, left: &Type, right: &Type) -> Result<Type, String> {
+    let error = |reason: &str| Err(format!(
+        "Cannot apply '{}' to types '{}' and '{}'{}", symbol(operator), left, right, reason
+    ));
+
+    let scalar = match (left.scalar(), right.scalar()) {
+        (Some(l), Some(r)) if l.is_numeric() && r.is_numeric() => match common_scalar(l, r) {
+            Some(scalar) => scalar,
+            None => return error(" (their component types cannot be implicitly converted to each other)"),
+        },
+
+        (Some(Scalar::Bool), _) | (_, Some(Scalar::Bool)) => return error(" (arithmetic is not defined for booleans)"),
+        _ => return error(" (only scalars, vectors, and matrices support arithmetic)"),
+    };
+
+    // Matrices only hold float or double components, so `2 * m` multiplies by a float
+    let matrix = |shape: &MatShape| Type::Matrix(MatShape { double: scalar == Scalar::Double, ..*shape });
+
+    match (left, right) {
+        (Type::Scalar(_), Type::Scalar(_)) => Ok(Type::Scalar(scalar)),
+
+        // Scalars apply to every component
+        (Type::Scalar(_), Type::Vector(_, size)) | (Type::Vector(_, size), Type::Scalar(_)) => Ok(Type::Vector(scalar, *size)),
+        (Type::Scalar(_), Type::Matrix(shape)) | (Type::Matrix(shape), Type::Scalar(_)) => Ok(matrix(shape)),
+
+        (Type::Vector(_, l), Type::Vector(_, r)) => {
+            if l == r {
+                Ok(Type::Vector(scalar, *l))
+            } else {
+                error(" (vectors must have the same number of components)")
+            }
+        }
+
+        // Linear algebraic product, `matCxR * matKxC = matKxR`
+        (Type::Matrix(l), Type::Matrix(r)) if matches!(operator, BinaryOperator::Multiply) => {
+            if l.columns == r.rows {
+                Ok(matrix(&MatShape { columns: r.columns, rows: l.rows, double: false }))
+            } else {
+                error(" (the left matrix must have as many columns as the right has rows)")
+            }
+        }
+
+        (Type::Matrix(l), Type::Matrix(r)) => {
+            if l.columns == r.columns && l.rows == r.rows {
+                Ok(matrix(l))
+            } else {
+                error(" (matrices must have the same dimensions)")
+            }
+        }
+
+        // `matCxR * vecC = vecR`, treating the vector as a column
+        (Type::Matrix(l), Type::Vector(_, size)) if matches!(operator, BinaryOperator::Multiply) => {
+            if *size == l.columns {
+                Ok(Type::Vector(scalar, l.rows))
+            } else {
+                error(&format!(" (the vector needs {} components, one per column)", l.columns))
+            }
+        }
+
+        // `vecR * matCxR = vecC`, treating the vector as a row
+        (Type::Vector(_, size), Type::Matrix(r)) if matches!(operator, BinaryOperator::Multiply) => {
+            if *size == r.rows {
+                Ok(Type::Vector(scalar, r.columns))
+            } else {
+                error(&format!(" (the vector needs {} components, one per row)", r.rows))
+            }
+        }
+
+        _ => error(" (matrices and vectors can only be multiplied)"),
+    }
+}
+
+/// `<`, `>`, `<=`, and `>=` only compare numeric scalars
+fn relational_type(operator: &BinaryOperator, left: &Type, right: &Type) -> Result<Type, String> {
+    match (left, right) {
+        (Type::Scalar(l), Type::Scalar(r)) if l.is_numeric() && r.is_numeric() && common_scalar(*l, *r).is_some() => {
+            Ok(Type::BOOL)
+        }
+
+        (Type::Vector(..), _) | (_, Type::Vector(..)) => {
+            let function = match operator {
+                BinaryOperator::GreaterThan => "greaterThan",
+                BinaryOperator::LessThan => "lessThan",
+                BinaryOperator::GreaterThanOrEqualTo => "greaterThanEqual",
+                _ => "lessThanEqual",
+            };
+
+            Err(format!(
+                "Cannot compare types '{}' and '{}' with '{}' (use '{}' to compare vectors component-wise)",
+                left, right, symbol(operator), function
+            ))
+        }
+
+        _ => Err(format!(
+            "Cannot compare types '{}' and '{}' with '{}' (only numeric scalars can be ordered)",
+            left, right, symbol(operator)
+        )),
+    }
+}
+
+/// `==` and `!=` compare whole values of the same type, after implicit conversion, and produce a single `bool`.
+/// Opaque types (samplers) cannot be compared
+fn equality_type(operator: &BinaryOperator, left: &Type, right: &Type) -> Result<Type, String> {
+    let comparable = match (left, right) {
+        (Type::Sampler(_), _) | (_, Type::Sampler(_)) => return Err(format!(
+            "Cannot compare types '{}' and '{}' with '{}' (samplers are opaque, so they cannot be compared)",
+            left, right, symbol(operator)
+        )),
+
+        (Type::Scalar(l), Type::Scalar(r)) => common_scalar(*l, *r).is_some(),
+        (Type::Vector(l, l_size), Type::Vector(r, r_size)) => l_size == r_size && common_scalar(*l, *r).is_some(),
+        (l, r) => l == r || super::castable(l, r) || super::castable(r, l),
+    };
+
+    if comparable {
+        Ok(Type::BOOL)
+    } else {
+        Err(format!(
+            "Cannot compare types '{}' and '{}' with '{}' (only values of the same type can be compared)",
+            left, right, symbol(operator)
+        ))
+    }
+}
@@ -0,0 +1,124 @@
+use crate::parse::context::ShaderType;
+use crate::parse::types::{Scalar, Type};
+
+// See section 7.1 (Built-In Language Variables) of the GLSL 4.60 specification
+// and https://www.khronos.org/opengl/wiki/Built-in_Variable_(GLSL)
+
+/// A `gl_` variable declared by GLSL for a stage
+pub struct BuiltinVariable {
+    pub name: &'static str,
+    /// Type of the variable, or of its elements if it is an array
+    ty: Type,
+    /// Size of arrays. GLSL leaves `gl_ClipDistance` and such unsized, so the guaranteed minimum is used
+    array: Option<u32>,
+    /// Outputs can be written to. Inputs are read-only
+    pub writable: bool,
+    /// Minimum GLSL version
+    pub since: u32,
+}
+
+impl BuiltinVariable {
+    pub fn ty(&self) -> Type {
+        match self.array {
+            Some(size) => Type::Array(Box::new(self.ty.clone()), size),
+            None => self.ty.clone(),
+        }
+    }
+}
+
+const fn input(name: &'static str, ty: Type, since: u32) -> BuiltinVariable {
+    BuiltinVariable { name, ty, array: None, writable: false, since }
+}
+
+const fn output(name: &'static str, ty: Type, since: u32) -> BuiltinVariable {
+    BuiltinVariable { name, ty, array: None, writable: true, since }
+}
+
+const fn input_array(name: &'static str, ty: Type, size: u32, since: u32) -> BuiltinVariable {
+    BuiltinVariable { name, ty, array: Some(size), writable: false, since }
+}
+
+const fn output_array(name: &'static str, ty: Type, size: u32, since: u32) -> BuiltinVariable {
+    BuiltinVariable { name, ty, array: Some(size), writable: true, since }
+}
+
+const VEC2: Type = Type::Vector(Scalar::Float, 2);
+const VEC4: Type = Type::Vector(Scalar::Float, 4);
+const UVEC3: Type = Type::Vector(Scalar::UInt, 3);
+
+/// `gl_MaxClipDistances` and `gl_MaxCullDistances` are at least 8
+const MAX_DISTANCES: u32 = 8;
+
+const VERTEX: &[BuiltinVariable] = &[
+    input("gl_VertexID", Type::INT, 130),
+    input("gl_InstanceID", Type::INT, 140),
+    input("gl_DrawID", Type::INT, 460),
+    input("gl_BaseVertex", Type::INT, 460),
+    input("gl_BaseInstance", Type::INT, 460),
+
+    output("gl_Position", VEC4, 110),
+    output("gl_PointSize", Type::FLOAT, 110),
+    output_array("gl_ClipDistance", Type::FLOAT, MAX_DISTANCES, 130),
+    output_array("gl_CullDistance", Type::FLOAT, MAX_DISTANCES, 450),
+];
+
+const FRAGMENT: &[BuiltinVariable] = &[
+    input("gl_FragCoord", VEC4, 110),
+    input("gl_FrontFacing", Type::BOOL, 110),
+    input("gl_PointCoord", VEC2, 110),
+    input_array("gl_ClipDistance", Type::FLOAT, MAX_DISTANCES, 130),
+    input_array("gl_CullDistance", Type::FLOAT, MAX_DISTANCES, 450),
+    input("gl_PrimitiveID", Type::INT, 150),
+    input("gl_SampleID", Type::INT, 400),
+    input("gl_SamplePosition", VEC2, 400),
+    // One bit per sample, so a single int covers up to 32 samples
+    input_array("gl_SampleMaskIn", Type::INT, 1, 400),
+    input("gl_Layer", Type::INT, 430),
+    input("gl_ViewportIndex", Type::INT, 430),
+    input("gl_HelperInvocation", Type::BOOL, 450),
+
+    output("gl_FragDepth", Type::FLOAT, 110),
+    output_array("gl_SampleMask", Type::INT, 1, 400),
+];
+
+const COMPUTE: &[BuiltinVariable] = &[
+    input("gl_NumWorkGroups", UVEC3, 430),
+    // A constant (declared with the `local_size` layout)
+    input("gl_WorkGroupSize", UVEC3, 430),
+    input("gl_WorkGroupID", UVEC3, 430),
+    input("gl_LocalInvocationID", UVEC3, 430),
+    input("gl_GlobalInvocationID", UVEC3, 430),
+    input("gl_LocalInvocationIndex", Type::UINT, 430),
+];
+
+/// Every built-in variable of a stage (regardless of version). Libraries have none
+pub fn builtin_variables(stage: &ShaderType) -> &'static [BuiltinVariable] {
+    match stage {
+        ShaderType::Vertex => VERTEX,
+        ShaderType::Fragment => FRAGMENT,
+        ShaderType::Compute => COMPUTE,
+        ShaderType::Library => &[],
+    }
+}
+
+/// Explains why a built-in variable cannot be used, if `name` is one
+pub fn unavailable(name: &str, stage: &ShaderType, version: u32) -> Option<String> {
+    if let Some(variable) = builtin_variables(stage).iter().find(|variable| variable.name == name) {
+        if variable.since > version {
+            return Some(format!("'{}' requires GLSL {} (compiling for GLSL {}, see '--glsl-version')", name, variable.since, version));
+        }
+
+        return None;
+    }
+
+    let stages: Vec<&str> = [(ShaderType::Vertex, "vertex"), (ShaderType::Fragment, "fragment"), (ShaderType::Compute, "compute")].iter()
+        .filter(|(stage, _)| builtin_variables(stage).iter().any(|variable| variable.name == name))
+        .map(|(_, stage_name)| *stage_name)
+        .collect();
+
+    if stages.is_empty() {
+        None
+    } else {
+        Some(format!("'{}' is only available in {} shaders", name, stages.join(" and ")))
+    }
+}
@@ -1,29 +1,18 @@
-use crate::parse::ast::TypeSpecifier;
+use crate::parse::types::{tuple_string, Scalar, Type};
 
 use super::castable;
 
-// TODO: Add the remaining vec types
-
 /// Returns whether a function is actually a vec constructor.
 /// Can also be used to check if a type is a vec type
 pub fn is_vec_constructor_or_type(name: &str) -> bool {
-    match name {
-        "bvec2" | "bvec3" | "bvec4" |
-        "ivec2" | "ivec3" | "ivec4" |
-        "uvec2" | "uvec3" | "uvec4" |
-        "vec2"  | "vec3"  | "vec4"  |
-        "dvec2" | "dvec3" | "dvec4" 
-          => true,
-
-        _ => false,
-    }
+    matches!(Type::from_name(name), Some(Type::Vector(..)))
 }
 
 /// Checks whether a given swizzle is valid for the vec type. Then checks whether the swizzle is assignable.
 /// If so, returns the swizzle type
-pub fn validate_swizzle_for_assignment(vec_type: &str, swizzle: &str) -> Result<String, String> {
+pub fn validate_swizzle_for_assignment(vec_type: &Type, swizzle: &str) -> Result<Type, String> {
     let swizzle_type = validate_swizzle(vec_type, swizzle)?;
-    
+
     if swizzle.len() == 1 {
         return Ok(swizzle_type);
     }
@@ -39,15 +28,15 @@ pub fn validate_swizzle_for_assignment(vec_type: &str, swizzle: &str) -> Result<
 }
 
 /// Checks whether a given swizzle is valid for the vec type. Returns the swizzle type if so
-pub fn validate_swizzle(vec_type: &str, swizzle: &str) -> Result<String, String> {
+pub fn validate_swizzle(vec_type: &Type, swizzle: &str) -> Result<Type, String> {
     if swizzle.len() > 4 {
         return Err("Error: Swizzle can only be up to four items in size".to_owned());
     }
 
-    let primitive = vec_primitive_type(vec_type);
-
-    // These unwraps should be guarenteed as safe at this point
-    let vec_size = vec_type.to_owned().pop().unwrap().to_digit(10).unwrap();
+    let (primitive, vec_size) = match vec_type {
+        Type::Vector(primitive, size) => (*primitive, *size),
+        _ => return Err(format!("Error: Type '{}' cannot be swizzled", vec_type)),
+    };
 
     // TODO: Allow more than just "xyzw" ?
 
@@ -60,7 +49,7 @@ pub fn validate_swizzle(vec_type: &str, swizzle: &str) -> Result<String, String>
 
             'z' => {
                 if vec_size < 3 {
-                    return Err(format!("Error: '{}' has no third component, y", vec_type));
+                    return Err(format!("Error: '{}' has no third component, z", vec_type));
                 }
             }
 
@@ -70,124 +59,48 @@ pub fn validate_swizzle(vec_type: &str, swizzle: &str) -> Result<String, String>
                 }
             }
 
-            _ => unreachable!(),
+            _ => return Err(format!("Error: '{}' has no component '{}' (only 'x', 'y', 'z', and 'w' are supported)", vec_type, field)),
         }
     }
 
     // Single element of the vec
-    if swizzle.len() == 1 {
-        Ok(primitive.to_owned())
-    } else {
-        let mut result = vec_type.to_owned();
-
-        result.pop();
-        result.push_str(&swizzle.len().to_string());
-
-        Ok(result)
-    }
-}
-
-/// Returns the base type of a vector
-fn vec_primitive_type(vec_type: &str) -> &'static str {
-    match vec_type {
-        "bvec2" | "bvec3" | "bvec4" => "bool",
-        "ivec2" | "ivec3" | "ivec4" => "int",
-        "uvec2" | "uvec3" | "uvec4" => "uint",
-        "vec2"  | "vec3"  | "vec4"  => "float",
-        "dvec2" | "dvec3" | "dvec4" => "double",
-
-        _ => unreachable!(),
-    }
+    Ok(Type::vector(primitive, swizzle.len()))
 }
 
 // See https://www.khronos.org/opengl/wiki/Data_Type_(GLSL)#Vector_constructors
 /// Returns vec type if the constructor is valid
-pub fn validate_constructor(vec_type: &str, passed: &Vec<TypeSpecifier>) -> Result<TypeSpecifier, String> {
-    let num_args = passed.len();
-    let primitive = vec_primitive_type(vec_type);
+pub fn validate_constructor(primitive: Scalar, size: usize, passed: &[Type]) -> Result<Type, String> {
+    let vec_type = Type::Vector(primitive, size);
+    let component = Type::Scalar(primitive);
 
-    if num_args == 0 {
+    if passed.is_empty() {
         return Err(format!("Error: Type '{}' must be initialized with values", vec_type));
     }
-    
-    // Special case for 'vec3(1.)' or similar
-    if num_args == 1 && castable(&passed[0].as_string(), vec_primitive_type(vec_type))? {
-        return Ok(TypeSpecifier::Identifier(vec_type.to_owned()));
-    }
 
-    match vec_type {
-        "bvec2" | "ivec2" | "uvec2" | "vec2" | "dvec2" => {
-            if num_args > 2 {
-                return Err(format!("Error: Too many arguments for '{}'", vec_type));
-            }
-            if !castable(&passed[0].as_string(), primitive)? || !castable(&passed[1].as_string(), primitive)? {
-                return Err(format!("Error: Both '{}' arguments must be castable to '{}'", vec_type, primitive));
-            }
-        }
-
-        v3 @ "bvec3" | v3 @ "ivec3" | v3 @ "uvec3" | v3 @ "vec3" | v3 @ "dvec3" => {
-            let mut v2 = v3.to_owned();
-            v2.pop(); v2.push('2');
-
-            if num_args > 3 {
-                return Err(format!("Error: Too many arguments for '{}'", vec_type));
-            }
-
-            // vec3 can be made of one vec2 and one primitive
-            if num_args == 2 && 
-                ! ( passed[0].as_string() == v2 && castable(&passed[1].as_string(), primitive)?
-                ||  passed[1].as_string() == v2 && castable(&passed[0].as_string(), primitive)? ) 
-            {
-                return Err(format!("Error: '{}' can be built from only one '{}' and one '{}' or three '{}'s", vec_type, v2, primitive, primitive));
-            }
-
-            if num_args == 3 && !(castable(&passed[0].as_string(), primitive)? && castable(&passed[1].as_string(), primitive)? && castable(&passed[2].as_string(), primitive)?) {
-                return Err(format!("Error: All three '{}' arguments must be castable to '{}'", vec_type, primitive));
-            }
-        }
-
-        v4 @ "bvec4" | v4 @ "ivec4" | v4 @ "uvec4" | v4 @ "vec4" | v4 @ "dvec4" => {
-            let mut v2 = v4.to_owned();
-            v2.pop(); v2.push('2');
-
-            let mut v3 = v4.to_owned();
-            v3.pop(); v3.push('3');
-
-            if num_args > 4 {
-                return Err(format!("Error: Too many arguments for '{}'", vec_type));
-            }
+    // Special cases for 'vec3(1.)' and 'vec3(v4)' (extra components are dropped)
+    match passed {
+        [ty] if castable(ty, &component) => return Ok(vec_type),
+        [Type::Vector(scalar, count)] if *scalar == primitive && *count >= size => return Ok(vec_type),
+        _ => {}
+    }
 
-            // vec4 can be made of one vec3 and one primitive
-            // or two vec2s
-            if num_args == 2 && 
-                ! ( passed[0].as_string() == v3 && castable(&passed[1].as_string(), primitive)?
-                ||  passed[1].as_string() == v3 && castable(&passed[0].as_string(), primitive)?
-                ||  passed[0].as_string() == v2 && passed[1].as_string() == v2 ) 
-            {
-                return Err(format!("Error: '{}' can be built from only two '{}'s, one '{}' and two '{}'s, one '{}' and one '{}', or four '{}'s", vec_type, v2, v2, primitive, v3, primitive, primitive));
-            }
-            
-            // vec4 cn be made of one vec2 and two primitives
-            if num_args == 3 && !(  (passed[0].as_string() == v2 && castable(&passed[1].as_string(), primitive)? && castable(&passed[2].as_string(), primitive)?) 
-            || (passed[1].as_string() == v2 && castable(&passed[0].as_string(), primitive)? && castable(&passed[2].as_string(), primitive)?)  
-            || (passed[2].as_string() == v2 && castable(&passed[0].as_string(), primitive)? && castable(&passed[1].as_string(), primitive)?) )
-            {
-                return Err(format!("Error: '{}' can be built from only two '{}'s, one '{}' and two '{}'s, one '{}' and one '{}', or four '{}'s", vec_type, v2, v2, primitive, v3, primitive, primitive));
-            }
+    // Otherwise each argument supplies one or more components (vectors must match exactly, like `vec4(v.xy, v.zw)`)
+    let mut components = 0;
+    for ty in passed {
+        components += match ty {
+            Type::Vector(scalar, count) if *scalar == primitive => *count,
+            _ if castable(ty, &component) => 1,
+            _ => return Err(format!("Error: '{}' cannot be built from type '{}' (got {})", vec_type, ty, tuple_string(passed))),
+        };
+    }
 
-            // vec4 can be made of four primitives
-            if num_args == 4 && 
-              !(castable(&passed[0].as_string(), primitive)? && castable(&passed[1].as_string(), primitive)? 
-                && castable(&passed[2].as_string(), primitive)? && castable(&passed[3].as_string(), primitive)?) 
-            {
-                return Err(format!("Error: All four '{}' arguments must be castable to '{}'", vec_type, primitive))
-            }
-        }
+    if components < size {
+        return Err(format!("Error: '{}' requires {} components, but only {} were given (got {})", vec_type, size, components, tuple_string(passed)));
+    }
 
-        _ => {
-            return Err(format!("Error: 'vec' type '{}' is not implemented", vec_type));
-        }
+    if components > size {
+        return Err(format!("Error: Too many arguments for '{}' (got {})", vec_type, tuple_string(passed)));
     }
 
-    Ok(TypeSpecifier::Identifier(vec_type.to_owned()))
-}
\ No newline at end of file
+    Ok(vec_type)
+}
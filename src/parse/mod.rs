@@ -1,6 +1,7 @@
 pub mod ast;
 pub mod context;
 pub mod glsl;
+pub mod types;
 
 lalrpop_mod!(pub parser, "/parse/parser.rs");
 
@@ -16,6 +17,13 @@ impl Input {
         
         let text = std::fs::read_to_string(&as_path)?;
 
+        Ok(Self::from_text(as_path, text))
+    }
+
+    /// Input that does not come from the file system (such as the standard library)
+    pub fn from_text<P: Into<std::path::PathBuf>>(path: P, text: String) -> Self {
+        let as_path = path.into();
+
         // TODO: Propogate errors somehow (instead of exit!)
         // TODO: It would probably be much better to do this check in the parser,
         //       then validate this from the AST (this also fixes above todo)
@@ -44,11 +52,11 @@ impl Input {
             }
         };
         
-        Ok(Self {
+        Self {
             path: as_path,
             text,
             shader_type,
-        })
+        }
     }
 
     pub fn reload_text(&mut self) {
@@ -0,0 +1,205 @@
+use crate::parse::ast::TypeSpecifier;
+use crate::parse::glsl::mat::{self, MatShape};
+
+// See https://www.khronos.org/opengl/wiki/Data_Type_(GLSL)
+
+/// Component type of scalars, vectors, and matrices
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Scalar {
+    Bool,
+    Int,
+    UInt,
+    Float,
+    Double,
+}
+
+impl Scalar {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scalar::Bool => "bool",
+            Scalar::Int => "int",
+            Scalar::UInt => "uint",
+            Scalar::Float => "float",
+            Scalar::Double => "double",
+        }
+    }
+
+    /// Prefix of the matching vector types (`bvec`, `ivec`, `uvec`, `vec`, `dvec`)
+    pub fn vector_prefix(&self) -> &'static str {
+        match self {
+            Scalar::Bool => "b",
+            Scalar::Int => "i",
+            Scalar::UInt => "u",
+            Scalar::Float => "",
+            Scalar::Double => "d",
+        }
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        *self != Scalar::Bool
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sampler {
+    Sampler2D,
+    Sampler3D,
+    SamplerCube,
+    Sampler2DArray,
+}
+
+impl Sampler {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sampler::Sampler2D => "sampler2D",
+            Sampler::Sampler3D => "sampler3D",
+            Sampler::SamplerCube => "samplerCube",
+            Sampler::Sampler2DArray => "sampler2DArray",
+        }
+    }
+}
+
+/// A validated type. Names have been resolved, so aliases and tuples no longer exist
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Type {
+    Void,
+    Scalar(Scalar),
+    /// `vecN`, `ivecN`, etc. with 2 to 4 components
+    Vector(Scalar, usize),
+    Matrix(MatShape),
+    /// Arrays of arrays are not supported
+    Array(Box<Type>, u32),
+    /// Identified by the struct's GLSL name (which is unique). Includes generated tuple structs
+    Struct(String),
+    Sampler(Sampler),
+}
+
+impl Type {
+    pub const BOOL: Type = Type::Scalar(Scalar::Bool);
+    pub const INT: Type = Type::Scalar(Scalar::Int);
+    pub const UINT: Type = Type::Scalar(Scalar::UInt);
+    pub const FLOAT: Type = Type::Scalar(Scalar::Float);
+    pub const DOUBLE: Type = Type::Scalar(Scalar::Double);
+
+    /// Parses the name of a builtin type. Struct names are not known here, so they are `None`
+    pub fn from_name(name: &str) -> Option<Type> {
+        let scalar = |name: &str| match name {
+            "bool" => Some(Scalar::Bool),
+            "int" => Some(Scalar::Int),
+            "uint" => Some(Scalar::UInt),
+            "float" => Some(Scalar::Float),
+            "double" => Some(Scalar::Double),
+            _ => None,
+        };
+
+        if let Some(scalar) = scalar(name) {
+            return Some(Type::Scalar(scalar));
+        }
+
+        if let Some(shape) = mat::shape(name) {
+            return Some(Type::Matrix(shape));
+        }
+
+        if let Some(index) = name.find("vec") {
+            let component = match &name[..index] {
+                "b" => Scalar::Bool,
+                "i" => Scalar::Int,
+                "u" => Scalar::UInt,
+                "" => Scalar::Float,
+                "d" => Scalar::Double,
+                _ => return None,
+            };
+
+            return match &name[index + 3..] {
+                "2" => Some(Type::Vector(component, 2)),
+                "3" => Some(Type::Vector(component, 3)),
+                "4" => Some(Type::Vector(component, 4)),
+                _ => None,
+            };
+        }
+
+        match name {
+            "void" => Some(Type::Void),
+            "sampler2D" => Some(Type::Sampler(Sampler::Sampler2D)),
+            "sampler3D" => Some(Type::Sampler(Sampler::Sampler3D)),
+            "samplerCube" => Some(Type::Sampler(Sampler::SamplerCube)),
+            "sampler2DArray" => Some(Type::Sampler(Sampler::Sampler2DArray)),
+            _ => None,
+        }
+    }
+
+    /// Converts a resolved TypeSpecifier, where any name that is not builtin is a struct
+    pub fn from_specifier(ty: &TypeSpecifier) -> Type {
+        let named = |name: &str| Type::from_name(name).unwrap_or_else(|| Type::Struct(name.to_owned()));
+
+        match ty {
+            TypeSpecifier::Identifier(name) => named(name),
+            TypeSpecifier::Array { ty, size } => Type::Array(Box::new(named(ty)), *size),
+            TypeSpecifier::Tuple(_) => unreachable!("tuple types are replaced by structs when resolved"),
+        }
+    }
+
+    /// The TypeSpecifier that declares a value of this type
+    pub fn specifier(&self) -> TypeSpecifier {
+        match self {
+            Type::Array(element, size) => TypeSpecifier::Array { ty: element.to_string(), size: *size },
+            ty => TypeSpecifier::Identifier(ty.to_string()),
+        }
+    }
+
+    /// A vector with `size` components (or a scalar if `size` is 1)
+    pub fn vector(scalar: Scalar, size: usize) -> Type {
+        if size == 1 {
+            Type::Scalar(scalar)
+        } else {
+            Type::Vector(scalar, size)
+        }
+    }
+
+    /// Component type of a scalar, vector, or matrix
+    pub fn scalar(&self) -> Option<Scalar> {
+        match self {
+            Type::Scalar(scalar) | Type::Vector(scalar, _) => Some(*scalar),
+            Type::Matrix(shape) => Some(shape.primitive()),
+            _ => None,
+        }
+    }
+
+    /// Number of components of a scalar or vector
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            Type::Scalar(_) => Some(1),
+            Type::Vector(_, size) => Some(*size),
+            _ => None,
+        }
+    }
+
+    /// Scalars, vectors, and matrices that are not boolean
+    pub fn is_numeric(&self) -> bool {
+        self.scalar().map_or(false, |scalar| scalar.is_numeric())
+    }
+
+    pub fn is_sampler(&self) -> bool {
+        matches!(self, Type::Sampler(_))
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Void => write!(f, "void"),
+            Type::Scalar(scalar) => write!(f, "{}", scalar.name()),
+            Type::Vector(scalar, size) => write!(f, "{}vec{}", scalar.vector_prefix(), size),
+            Type::Matrix(shape) => write!(f, "{}", shape.name()),
+            Type::Array(element, size) => write!(f, "{}[{}]", element, size),
+            Type::Struct(name) => write!(f, "{}", name),
+            Type::Sampler(sampler) => write!(f, "{}", sampler.name()),
+        }
+    }
+}
+
+/// Formats types like a tuple, `(float, vec2)`. Used for tuples and argument lists
+pub fn tuple_string(types: &[Type]) -> String {
+    let names: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+    format!("({})", names.join(", "))
+}
@@ -17,7 +17,7 @@ pub struct Application {
 
 impl Application {
     /// Set up an SDL2 application using OpenGL
-    pub fn new<P: Into<PathBuf>>(gl_version: (u8, u8), fragment_shader_path: P) -> Self {
+    pub fn new<P: Into<PathBuf>>(gl_version: (u8, u8), fragment_shader_path: P, options: crate::translate::Options) -> Self {
         let sdl2_context = sdl2::init().expect("Failed to initialize SDL2");
         let video_subsystem = sdl2_context.video().expect("Failed to initialize video subsystem");
     
@@ -49,10 +49,15 @@ impl Application {
             window,
             gl_context,
             timer,
-            runtime: super::Runtime::new(fragment_shader_path),
+            runtime: super::Runtime::new(fragment_shader_path, options),
         }
     }
 
+    /// Set uniforms from a file of `name = value, ...` lines whenever the shader is loaded
+    pub fn set_uniform_file<P: Into<PathBuf>>(&mut self, path: P) {
+        self.runtime.set_uniform_file(path);
+    }
+
     /// Start the windowed application
     pub fn run(&mut self) {
         // Initialize the fragment shader
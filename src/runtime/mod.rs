@@ -16,9 +16,18 @@ use std::collections::{HashSet, HashMap};
 
 pub struct Runtime {
     sdf_path: PathBuf,
+
+    /// Compilation settings used every time the shader is reloaded
+    options: crate::translate::Options,
     
     /// name -> (location, type)
     uniforms: HashMap<String, (usize, parse::ast::TypeSpecifier)>,
+    /// Annotations of each uniform (used to clamp values set from outside of the shader)
+    uniform_meta: HashMap<String, parse::context::UniformMeta>,
+    /// Uniforms the shader never reads, which are not bound
+    inactive_uniforms: HashSet<String>,
+    /// Lines of `name = value, ...` which are set after every reload
+    uniform_file: Option<PathBuf>,
 
     features: HashSet<String>,
 
@@ -27,13 +36,17 @@ pub struct Runtime {
 }
 
 impl Runtime {
-    pub fn new<P: Into<PathBuf>>(sdf_path: P) -> Self {
+    pub fn new<P: Into<PathBuf>>(sdf_path: P, options: crate::translate::Options) -> Self {
         let dummy_vao = opengl::DummyVAO::new();
         dummy_vao.bind();
 
         Runtime {
             sdf_path: sdf_path.into(),
+            options,
             uniforms: HashMap::new(),
+            uniform_meta: HashMap::new(),
+            inactive_uniforms: HashSet::new(),
+            uniform_file: None,
             features: HashSet::new(),
             _dummy_vao: dummy_vao,
         }
@@ -57,7 +70,7 @@ impl Runtime {
             return;
         }
 
-        let context = crate::translate::validate(ast.as_mut().unwrap(), &input);
+        let context = crate::translate::validate(ast.as_mut().unwrap(), &input, &self.options);
         if context.is_err() {
             println!("\nA shader error prevented reloading: ");
             println!("{}\n", context.err().unwrap());
@@ -65,6 +78,10 @@ impl Runtime {
         }
 
         let c = context.unwrap();
+        for warning in c.warnings() {
+            println!("Warning: {}\n", warning);
+        }
+
         let glsl = crate::translate::translate(&ast.unwrap(), &c);
 
         let default_vertex_shader = opengl::Shader::from_vertex_source(
@@ -81,8 +98,83 @@ impl Runtime {
 
         c.get_uniform_map(&mut self.uniforms);
         c.get_features(&mut self.features);
+        self.uniform_meta = c.uniforms().iter().map(|u| (u.name.clone(), u.meta.clone())).collect();
+        self.inactive_uniforms = c.uniforms().iter().filter(|u| !u.active).map(|u| u.name.clone()).collect();
 
         gl_program.set_used();
+        self.apply_uniform_file();
+    }
+
+    /// Values in the file are set whenever the shader is (re)loaded
+    pub fn set_uniform_file<P: Into<PathBuf>>(&mut self, path: P) {
+        self.uniform_file = Some(path.into());
+    }
+
+    fn apply_uniform_file(&mut self) {
+        let path = match &self.uniform_file {
+            Some(path) => path.clone(),
+            None => return,
+        };
+
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("\nCould not read the uniform file '{}': {}", path.display(), e);
+                return;
+            }
+        };
+
+        for (number, line) in text.lines().enumerate() {
+            // '#' starts a comment
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Err(e) = parse_uniform_line(line).and_then(|(name, values)| self.set_uniform(&name, &values)) {
+                println!("{}:{}: {}", path.display(), number + 1, e);
+            }
+        }
+    }
+
+    /// Set a user-declared uniform. Values are clamped to the uniform's `min` and `max`.
+    /// Uniforms that the shader never reads are accepted, but not bound
+    pub fn set_uniform(&mut self, name: &str, values: &[f32]) -> Result<(), String> {
+        let (location, ty) = self.uniforms.get(name).ok_or(format!("No such uniform exists, '{}'", name))?;
+        let (location, ty) = (*location as GLint, ty.as_string());
+
+        let meta = self.uniform_meta.get(name).cloned().unwrap_or_default();
+        let values: Vec<f32> = values.iter().map(|v| meta.clamp(*v as f64) as f32).collect();
+
+        let expected = match ty.as_str() {
+            "float" | "int" | "uint" | "bool" => 1,
+            "vec2" => 2,
+            "vec3" => 3,
+            "vec4" => 4,
+            _ => return Err(format!("Uniform '{}' has type '{}', which cannot be set by the runtime", name, ty)),
+        };
+
+        if values.len() != expected {
+            return Err(format!("Uniform '{}' of type '{}' requires {} values, but {} were given", name, ty, expected, values.len()));
+        }
+
+        if self.inactive_uniforms.contains(name) {
+            return Ok(());
+        }
+
+        unsafe {
+            match ty.as_str() {
+                "float" => gl::Uniform1f(location, values[0]),
+                "int" | "bool" => gl::Uniform1i(location, values[0] as GLint),
+                "uint" => gl::Uniform1ui(location, values[0] as GLuint),
+                "vec2" => gl::Uniform2fv(location, 1, values.as_ptr()),
+                "vec3" => gl::Uniform3fv(location, 1, values.as_ptr()),
+                "vec4" => gl::Uniform4fv(location, 1, values.as_ptr()),
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(())
     }
 
     fn get_uniform_location(&self, name: &str) -> Result<i32, String> {
@@ -117,4 +209,19 @@ impl Runtime {
             gl::DrawArrays(gl::TRIANGLES, 0, 3);
         }
     }
-}
\ No newline at end of file
+}
+
+/// `name = 1.0, 2.0, ...` (booleans are `true` or `false`)
+fn parse_uniform_line(line: &str) -> Result<(String, Vec<f32>), String> {
+    let mut split = line.splitn(2, '=');
+    let name = split.next().unwrap().trim();
+    let values = split.next().ok_or(format!("Expected 'name = value, ...' (found '{}')", line))?;
+
+    let values = values.split(',').map(|value| match value.trim() {
+        "true" => Ok(1.),
+        "false" => Ok(0.),
+        value => value.parse::<f32>().map_err(|_| format!("Uniform '{}' was given an invalid value '{}'", name, value)),
+    }).collect::<Result<Vec<f32>, String>>()?;
+
+    Ok((name.to_owned(), values))
+}
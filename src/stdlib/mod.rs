@@ -0,0 +1,37 @@
+// The standard library, compiled into the crate
+//
+// `import std::sdf;` is resolved here instead of on disk, so the library always
+// matches the compiler it ships with.
+
+/// Version of the standard library (always the compiler's version)
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Name of the root module reserved for the standard library
+pub const ROOT: &str = "std";
+
+/// Source of every standard library module (module name, source)
+pub const MODULES: &[(&str, &str)] = &[
+    ("sdf", include_str!("sdf.sdf")),
+    ("noise", include_str!("noise.sdf")),
+    ("color", include_str!("color.sdf")),
+    ("camera", include_str!("camera.sdf")),
+];
+
+/// Source of a standard library module, given the import path after `std::`
+pub fn source(path: &[String]) -> Option<&'static str> {
+    match path {
+        [module] => MODULES.iter().find(|(name, _)| name == module).map(|(_, source)| *source),
+        _ => None,
+    }
+}
+
+/// Path used to identify a standard library module in diagnostics
+pub fn virtual_path(path: &[String]) -> std::path::PathBuf {
+    let mut virtual_path = std::path::PathBuf::from(format!("<{} {}>", ROOT, VERSION));
+    for segment in path {
+        virtual_path.push(segment);
+    }
+    virtual_path.set_extension("sdf");
+
+    virtual_path
+}
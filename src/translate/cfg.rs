@@ -0,0 +1,165 @@
+use crate::parse::ast::*;
+use crate::parse::context::{Context, ShaderType};
+
+// Conditional compilation
+//
+// `@cfg(...)` items/statements and `if const` blocks are parsed like everything else,
+// then resolved here before validation. Disabled code is removed from the AST, so it is
+// neither validated nor emitted.
+
+/// Whether a predicate holds for the defines and shader type of the given context
+pub fn evaluate(predicate: &CfgPredicate, context: &Context) -> bool {
+    match predicate {
+        CfgPredicate::Defined(key) => {
+            key == "shader" || context.options.defines.iter().any(|(k, _v)| k == key)
+        }
+
+        CfgPredicate::Equals { key, value } => {
+            if key == "shader" {
+                return value == shader_type_name(&context.shader_type);
+            }
+
+            context.options.defines.iter().any(|(k, v)| k == key && v == value)
+        }
+
+        CfgPredicate::All(predicates) => predicates.iter().all(|p| evaluate(p, context)),
+        CfgPredicate::Any(predicates) => predicates.iter().any(|p| evaluate(p, context)),
+        CfgPredicate::Not(predicate) => !evaluate(predicate, context),
+    }
+}
+
+/// Value of the built-in `shader` key
+fn shader_type_name(shader_type: &ShaderType) -> &'static str {
+    match shader_type {
+        ShaderType::Vertex => "vertex",
+        ShaderType::Fragment => "fragment",
+        ShaderType::Compute => "compute",
+        ShaderType::Library => "library",
+    }
+}
+
+/// Remove disabled items and unwrap enabled ones
+pub fn resolve_items(items: &mut Vec<Item>, context: &Context) {
+    let mut resolved = Vec::new();
+
+    for item in items.drain(..) {
+        if let Some(mut item) = resolve_item(item, context) {
+            match attributed_mut(&mut item) {
+                Item::Function { statements, .. } | Item::Scene { statements, .. } => {
+                    resolve_statements(statements, context);
+                }
+
+                Item::Implementation { functions, .. } => {
+                    resolve_items(functions, context);
+                }
+
+                _ => {}
+            }
+
+            resolved.push(item);
+        }
+    }
+
+    *items = resolved;
+}
+
+fn resolve_item(item: Item, context: &Context) -> Option<Item> {
+    match item {
+        Item::Cfg { predicate, item, span: _ } => {
+            if evaluate(&predicate, context) {
+                // Items may have several `@cfg`s
+                resolve_item(*item, context)
+            } else {
+                None
+            }
+        }
+
+        // Lint attributes go away along with their item
+        Item::Lint { level, lints, item: Some(item), span } => {
+            resolve_item(*item, context).map(|item| Item::Lint { level, lints, item: Some(Box::new(item)), span })
+        }
+
+        _ => Some(item),
+    }
+}
+
+/// The item under any lint attributes
+fn attributed_mut(item: &mut Item) -> &mut Item {
+    match item {
+        Item::Lint { item: Some(item), .. } => attributed_mut(item),
+        item => item,
+    }
+}
+
+/// Remove disabled statements and replace `if const` with the selected block
+pub fn resolve_statements(statements: &mut Vec<Statement>, context: &Context) {
+    let mut resolved = Vec::new();
+
+    for statement in statements.drain(..) {
+        if let Some(mut statement) = resolve_statement(statement, context) {
+            match &mut statement {
+                Statement::For { block, .. } | Statement::While { block, .. } => {
+                    resolve_statements(block, context);
+                }
+
+                Statement::Block { statements, .. } => {
+                    resolve_statements(statements, context);
+                }
+
+                Statement::Expression { expression, .. } => {
+                    resolve_expression(expression, context);
+                }
+
+                _ => {}
+            }
+
+            resolved.push(statement);
+        }
+    }
+
+    *statements = resolved;
+}
+
+fn resolve_statement(statement: Statement, context: &Context) -> Option<Statement> {
+    match statement {
+        Statement::Cfg { predicate, statement, span: _ } => {
+            if evaluate(&predicate, context) {
+                resolve_statement(*statement, context)
+            } else {
+                None
+            }
+        }
+
+        Statement::IfConst { predicate, if_block, else_block, span } => {
+            if evaluate(&predicate, context) {
+                Some(Statement::Block { statements: if_block, span })
+            } else if let Some(mut else_statements) = else_block {
+                // `else if const` chains do not need a nested block
+                if let [Statement::IfConst { .. }] = else_statements.as_slice() {
+                    return resolve_statement(else_statements.pop().unwrap(), context);
+                }
+
+                Some(Statement::Block { statements: else_statements, span })
+            } else {
+                None
+            }
+        }
+
+        _ => Some(statement),
+    }
+}
+
+// Only `if` expressions contain statements
+fn resolve_expression(expression: &mut Expression, context: &Context) {
+    if let Expression::If { if_block, else_block, else_if_block, .. } = expression {
+        resolve_statements(if_block, context);
+
+        if let Some(else_statements) = else_block {
+            resolve_statements(else_statements, context);
+        }
+
+        if let Some(else_if) = else_if_block {
+            resolve_expression(else_if, context);
+        }
+    }
+}
@@ -0,0 +1,520 @@
+use crate::parse::ast::*;
+use crate::parse::context::Context;
+use crate::parse::types::Type;
+use crate::parse::Input;
+
+use std::collections::HashSet;
+
+// Control flow analysis
+//
+// Runs on validated function bodies. Non-void functions must return a value on every path,
+// since GLSL leaves the result of falling off the end undefined (and some drivers reject it).
+// Statements that can never run are reported by the `unreachable_code` lint.
+//
+// `out` parameters start out undefined, so they must be written on every path before the function
+// returns, and cannot be read before they are written. Only assigning the whole value (or passing it to
+// an `out` or `inout` parameter) counts as writing it, since writing a field, element, or swizzle leaves the
+// rest undefined.
+
+/// How a statement leaves the block containing it, when it always does
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Exit {
+    /// `return`, or a loop that never ends
+    Return,
+    /// `break` or `continue`, which only leave the enclosing loop
+    Jump,
+}
+
+/// Ensure that a function producing `return_type` cannot reach the end of its body
+pub fn check_function(name: &str, return_type: &Type, statements: &[Statement], span: Span, input: &Input) -> Result<(), String> {
+    let exit = block_exit(statements, &mut Vec::new());
+
+    if *return_type != Type::Void && exit.is_none() {
+        return Err(format!(
+            "{}\nNot all paths in function '{}' return a value (expected '{}')",
+            input.evaluate_span(span), name, return_type
+        ));
+    }
+
+    Ok(())
+}
+
+/// Statements followed by code that can never run, with a description of why
+pub fn unreachable_code(statements: &[Statement]) -> Vec<(Span, &'static str)> {
+    let mut unreachable = Vec::new();
+    block_exit(statements, &mut unreachable);
+
+    unreachable
+}
+
+/// How a list of statements always exits, along with the statement responsible
+fn block_exit(statements: &[Statement], unreachable: &mut Vec<(Span, &'static str)>) -> Option<(Exit, Span)> {
+    for (i, statement) in statements.iter().enumerate() {
+        if let Some((exit, span)) = statement_exit(statement, unreachable) {
+            if i + 1 < statements.len() {
+                let cause = match statement {
+                    Statement::Return { .. } => "'return'",
+                    Statement::Break(_) => "'break'",
+                    Statement::Continue(_) => "'continue'",
+                    Statement::While { .. } => "a loop that never ends",
+                    _ => "an 'if' whose branches all exit",
+                };
+
+                unreachable.push((span, cause));
+            }
+
+            return Some((exit, span));
+        }
+    }
+
+    None
+}
+
+fn statement_exit(statement: &Statement, unreachable: &mut Vec<(Span, &'static str)>) -> Option<(Exit, Span)> {
+    match statement {
+        Statement::Return { span, .. } => Some((Exit::Return, *span)),
+        Statement::Break(span) | Statement::Continue(span) => Some((Exit::Jump, *span)),
+
+        Statement::Expression { expression: expression @ Expression::If { .. }, span } => {
+            if_exit(expression, unreachable).map(|exit| (exit, *span))
+        }
+
+        Statement::Block { statements, .. } => block_exit(statements, unreachable),
+
+        // The body may run zero times
+        Statement::For { block, .. } => {
+            block_exit(block, unreachable);
+            None
+        }
+
+        Statement::While { condition, block, do_while } => {
+            let body = block_exit(block, unreachable);
+
+            // `while true` without a `break` only leaves through `return`
+            let endless = matches!(condition.expression, Expression::Literal(Literal::Bool(true)));
+            if endless && !breaks(block) {
+                return Some((Exit::Return, condition.span));
+            }
+
+            // A do-while body runs at least once
+            match body {
+                Some((Exit::Return, span)) if *do_while => Some((Exit::Return, span)),
+                _ => None,
+            }
+        }
+
+        _ => None,
+    }
+}
+
+/// An if chain exits only if every branch does (so it needs a final `else`)
+fn if_exit(expression: &Expression, unreachable: &mut Vec<(Span, &'static str)>) -> Option<Exit> {
+    if let Expression::If { if_block, else_block, else_if_block, .. } = expression {
+        let then_exit = block_exit(if_block, unreachable).map(|(exit, _)| exit);
+
+        let else_exit = if let Some(else_if) = else_if_block {
+            if_exit(else_if, unreachable)
+        } else if let Some(else_block) = else_block {
+            block_exit(else_block, unreachable).map(|(exit, _)| exit)
+        } else {
+            None
+        };
+
+        match (then_exit, else_exit) {
+            (Some(Exit::Return), Some(Exit::Return)) => Some(Exit::Return),
+            (Some(_), Some(_)) => Some(Exit::Jump),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// Whether a loop body contains a `break` for that loop (not for a nested one)
+fn breaks(statements: &[Statement]) -> bool {
+    statements.iter().any(|statement| match statement {
+        Statement::Break(_) => true,
+        Statement::Block { statements, .. } => breaks(statements),
+
+        Statement::Expression { expression, .. } => if_breaks(expression),
+
+        _ => false,
+    })
+}
+
+fn if_breaks(expression: &Expression) -> bool {
+    if let Expression::If { if_block, else_block, else_if_block, .. } = expression {
+        breaks(if_block)
+            || else_block.as_ref().map_or(false, |block| breaks(block))
+            || else_if_block.as_ref().map_or(false, |else_if| if_breaks(else_if))
+    } else {
+        false
+    }
+}
+
+/// `out` parameters written on every path to the current statement, or `None` when it cannot be reached
+type Written = Option<HashSet<String>>;
+
+/// State where two paths meet. Only what both wrote is known to be written
+fn join(a: Written, b: Written) -> Written {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.intersection(&b).cloned().collect()),
+        (written, None) | (None, written) => written,
+    }
+}
+
+/// Ensure that the `out` parameters of a validated function are written before they are read, and before it returns
+pub fn check_outputs(name: &str, parameters: &[(Option<FuncParamQualifier>, String, TypeSpecifier)], statements: &[Statement], span: Span, context: &Context, input: &Input) -> Result<(), String> {
+    let outputs: Vec<String> = parameters.iter()
+        .filter(|(qualifier, ..)| *qualifier == Some(FuncParamQualifier::Out))
+        .map(|(_, name, _)| name.clone())
+        .collect();
+
+    if outputs.is_empty() {
+        return Ok(());
+    }
+
+    let mut analysis = Outputs {
+        function: name,
+        outputs,
+        hidden: Vec::new(),
+        written: Some(HashSet::new()),
+        partial: HashSet::new(),
+        loops: Vec::new(),
+        context,
+        input,
+    };
+
+    analysis.block(statements)?;
+    analysis.check_returned(span)
+}
+
+struct Outputs<'a> {
+    function: &'a str,
+    outputs: Vec<String>,
+    /// Locals that hide an `out` parameter, for each nested scope
+    hidden: Vec<Vec<String>>,
+    written: Written,
+    /// `out` parameters that were partially written on some path, to explain why that does not count
+    partial: HashSet<String>,
+    /// States at the `break`s and `continue`s of each enclosing loop
+    loops: Vec<(Written, Written)>,
+    context: &'a Context,
+    input: &'a Input,
+}
+
+impl Outputs<'_> {
+    fn is_output(&self, name: &str) -> bool {
+        self.outputs.iter().any(|output| output == name) && !self.hidden.iter().flatten().any(|local| local == name)
+    }
+
+    fn hide(&mut self, name: &str) {
+        if self.outputs.iter().any(|output| output == name) {
+            self.hidden.last_mut().unwrap().push(name.to_owned());
+        }
+    }
+
+    fn read(&self, name: &str, span: Span) -> Result<(), String> {
+        match &self.written {
+            Some(written) if self.is_output(name) && !written.contains(name) => Err(format!(
+                "{}\n'out' parameter '{}' is read before it is written{}", self.input.evaluate_span(span), name, self.partial_hint(name)
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn write(&mut self, name: &str) {
+        if self.is_output(name) {
+            if let Some(written) = &mut self.written {
+                written.insert(name.to_owned());
+            }
+        }
+    }
+
+    fn partial_hint(&self, name: &str) -> &'static str {
+        if self.partial.contains(name) {
+            " (writing some of its fields, elements, or components does not count)"
+        } else {
+            ""
+        }
+    }
+
+    /// Writes to a place, which only count when they replace the whole variable
+    fn write_place(&mut self, place: Option<(String, bool)>) {
+        match place {
+            Some((variable, true)) => self.write(&variable),
+            Some((variable, false)) if self.is_output(&variable) => { self.partial.insert(variable); }
+            _ => {}
+        }
+    }
+
+    fn check_returned(&self, span: Span) -> Result<(), String> {
+        if let Some(written) = &self.written {
+            if let Some(output) = self.outputs.iter().find(|output| !written.contains(*output)) {
+                return Err(format!(
+                    "{}\n'out' parameter '{}' of '{}' is not written on every path before the function returns{}",
+                    self.input.evaluate_span(span), output, self.function, self.partial_hint(output)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn block(&mut self, statements: &[Statement]) -> Result<(), String> {
+        self.hidden.push(Vec::new());
+
+        for statement in statements {
+            self.statement(statement)?;
+        }
+
+        self.hidden.pop();
+        Ok(())
+    }
+
+    fn statement(&mut self, statement: &Statement) -> Result<(), String> {
+        match statement {
+            Statement::Let { ident, expression, .. } => {
+                if let Some(value) = expression {
+                    self.expression(&value.expression, value.span)?;
+                }
+
+                self.hide(ident);
+            }
+
+            Statement::LetConstructor { ident, constructor, .. } => {
+                for (_field, value) in &constructor.fields {
+                    self.expression(&value.expression, value.span)?;
+                }
+
+                self.hide(ident);
+            }
+
+            Statement::LetPattern { pattern, expression, temp, .. } => {
+                self.expression(&expression.expression, expression.span)?;
+                self.hide(temp);
+                self.hide_pattern(pattern);
+            }
+
+            Statement::Constant(constant) => {
+                self.expression(&constant.value.expression, constant.value.span)?;
+                self.hide(&constant.ident);
+            }
+
+            // Compound assignments read the old value
+            Statement::Assignment { lhs, op, expression } => {
+                self.expression(&expression.expression, expression.span)?;
+
+                let place = self.place(&lhs.expression, lhs.span, !matches!(op, AssignmentOperator::Assign))?;
+                self.write_place(place);
+            }
+
+            Statement::Return { expression, span } => {
+                if let Some(value) = expression {
+                    self.expression(&value.expression, value.span)?;
+                }
+
+                self.check_returned(*span)?;
+                self.written = None;
+            }
+
+            // The body may run zero times, so it cannot write anything for the code after it
+            Statement::For { loop_var, from, to, block } => {
+                self.expression(&from.expression, from.span)?;
+                self.expression(&to.expression, to.span)?;
+
+                let entry = self.written.clone();
+
+                self.hidden.push(Vec::new());
+                self.hide(loop_var);
+                self.loop_body(block)?;
+                self.hidden.pop();
+
+                self.written = entry;
+            }
+
+            Statement::While { condition, block, do_while: false } => {
+                self.expression(&condition.expression, condition.span)?;
+
+                let entry = self.written.clone();
+                let (breaks, _) = self.loop_body(block)?;
+
+                // `while true` only ends through `break`
+                self.written = match condition.expression {
+                    Expression::Literal(Literal::Bool(true)) => breaks,
+                    _ => entry,
+                };
+            }
+
+            // The body runs at least once, and the condition is checked after it (or after `continue`)
+            Statement::While { condition, block, do_while: true } => {
+                let (breaks, continues) = self.loop_body(block)?;
+
+                self.written = join(self.written.take(), continues);
+                self.expression(&condition.expression, condition.span)?;
+                self.written = join(self.written.take(), breaks);
+            }
+
+            Statement::Break(_) => {
+                let written = self.written.take();
+                let (breaks, _) = self.loops.last_mut().unwrap();
+                *breaks = join(breaks.take(), written);
+            }
+
+            Statement::Continue(_) => {
+                let written = self.written.take();
+                let (_, continues) = self.loops.last_mut().unwrap();
+                *continues = join(continues.take(), written);
+            }
+
+            Statement::Expression { expression, span } => self.expression(expression, *span)?,
+            Statement::Block { statements, .. } => self.block(statements)?,
+
+            Statement::Cfg { .. } | Statement::IfConst { .. } => unreachable!("cfg statements are resolved before validation"),
+        }
+
+        Ok(())
+    }
+
+    /// Analyze a loop body, returning the states at its `break`s and `continue`s
+    fn loop_body(&mut self, statements: &[Statement]) -> Result<(Written, Written), String> {
+        self.loops.push((None, None));
+        self.block(statements)?;
+
+        Ok(self.loops.pop().unwrap())
+    }
+
+    fn hide_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Binding { ident, .. } => self.hide(ident),
+            Pattern::Tuple(patterns) | Pattern::Vector(patterns) => patterns.iter().for_each(|pattern| self.hide_pattern(pattern)),
+            Pattern::Struct { fields, .. } => fields.iter().for_each(|(_field, pattern)| self.hide_pattern(pattern)),
+        }
+    }
+
+    /// Evaluate the parts of a place that are read (indices, and the variable itself if `read`), returning
+    /// the variable that is written and whether all of it is. Values that are not places are only read
+    fn place(&mut self, expression: &Expression, span: Span, read: bool) -> Result<Option<(String, bool)>, String> {
+        match expression {
+            Expression::Identifier(name) => {
+                if read {
+                    self.read(name, span)?;
+                }
+
+                Ok(Some((name.clone(), true)))
+            }
+
+            Expression::Member(member) => match member.path.first() {
+                Some(IdentOrFunction::Ident(name)) if member.path.len() > 1 => {
+                    let place = self.place(&Expression::Identifier(name.clone()), span, read)?;
+                    Ok(place.map(|(variable, _)| (variable, false)))
+                }
+
+                Some(IdentOrFunction::Ident(name)) => self.place(&Expression::Identifier(name.clone()), span, read),
+                _ => self.expression(expression, span).map(|_| None),
+            },
+
+            Expression::Unary { operator: UnaryOperator::Index(index), expr, .. } => {
+                self.expression(index, span)?;
+
+                let place = self.place(expr, span, read)?;
+                Ok(place.map(|(variable, _)| (variable, false)))
+            }
+
+            Expression::Parenthesized(expr) => self.place(expr, span, read),
+
+            _ => self.expression(expression, span).map(|_| None),
+        }
+    }
+
+    /// Arguments are evaluated before the call writes to its `out` and `inout` parameters
+    fn call(&mut self, name: &str, arguments: &[Expression], span: Span) -> Result<(), String> {
+        let mut written = Vec::new();
+
+        for (index, argument) in arguments.iter().enumerate() {
+            match self.context.parameter_qualifier(name, index) {
+                FuncParamQualifier::In => self.expression(argument, span)?,
+                FuncParamQualifier::Out => written.extend(self.place(argument, span, false)?),
+                FuncParamQualifier::InOut => written.extend(self.place(argument, span, true)?),
+            }
+        }
+
+        for place in written {
+            self.write_place(Some(place));
+        }
+
+        Ok(())
+    }
+
+    fn expression(&mut self, expression: &Expression, span: Span) -> Result<(), String> {
+        match expression {
+            Expression::Literal(_) => {}
+            Expression::Identifier(name) => self.read(name, span)?,
+            Expression::Parenthesized(expr) => self.expression(expr, span)?,
+
+            Expression::ArrayConstructor { expressions, .. } | Expression::Tuple { expressions, .. } => {
+                for expr in expressions {
+                    self.expression(expr, span)?;
+                }
+            }
+
+            // The right side of a cast names a type
+            Expression::Binary { lhs, operator: BinaryOperator::Cast, .. } => self.expression(lhs, span)?,
+
+            // The right side of `&&` and `||` may not run, so its writes do not count
+            Expression::Binary { lhs, operator: BinaryOperator::And | BinaryOperator::Or, rhs, .. } => {
+                self.expression(lhs, span)?;
+
+                let entry = self.written.clone();
+                self.expression(rhs, span)?;
+                self.written = entry;
+            }
+
+            Expression::Binary { lhs, rhs, .. } => {
+                self.expression(lhs, span)?;
+                self.expression(rhs, span)?;
+            }
+
+            Expression::Unary { operator, expr, .. } => {
+                if let UnaryOperator::Index(index) = operator {
+                    self.expression(index, span)?;
+                }
+
+                self.expression(expr, span)?;
+            }
+
+            Expression::FunctionApply(FunctionApply { name, parameters, .. }) | Expression::FunctionCall(FunctionCall { name, parameters, .. }) => {
+                self.call(name, parameters, span)?;
+            }
+
+            // Method receivers are moved into the call's arguments during validation
+            Expression::Member(member) => {
+                for (i, item) in member.path.iter().enumerate() {
+                    match item {
+                        IdentOrFunction::Ident(name) if i == 0 => self.read(name, span)?,
+                        IdentOrFunction::Ident(_) => {}
+                        IdentOrFunction::Function(call) => self.call(&call.name, &call.parameters, span)?,
+                    }
+                }
+            }
+
+            Expression::If { expression: condition, if_block, else_block, else_if_block, .. } => {
+                self.expression(condition, span)?;
+
+                let entry = self.written.clone();
+                self.block(if_block)?;
+                let then_written = std::mem::replace(&mut self.written, entry);
+
+                if let Some(else_if) = else_if_block {
+                    self.expression(else_if, span)?;
+                } else if let Some(else_block) = else_block {
+                    self.block(else_block)?;
+                }
+
+                self.written = join(then_written, self.written.take());
+            }
+        }
+
+        Ok(())
+    }
+}
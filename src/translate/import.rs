@@ -0,0 +1,85 @@
+use super::Options;
+use crate::parse::Input;
+use crate::stdlib;
+
+use std::path::{Path, PathBuf};
+
+// Import resolution
+//
+// `import shapes::primitives;` refers to `shapes/primitives.sdf`, which is searched for
+// relative to the importing file, then in every `--lib-path`, then in every `SDF_PATH` entry.
+// Imports starting with `std::` always refer to the bundled standard library.
+
+/// Read the library referred to by an import path
+pub fn load_library(path: &[String], importing_file: &Path, options: &Options) -> Result<Input, String> {
+    if path[0] == stdlib::ROOT {
+        return match stdlib::source(&path[1..]) {
+            Some(source) => Ok(Input::from_text(stdlib::virtual_path(&path[1..]), source.to_owned())),
+            None => {
+                let modules = stdlib::MODULES.iter()
+                    .map(|(name, _)| format!("{}::{}", stdlib::ROOT, name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                Err(format!("'{}' is not part of the standard library (available: {})", module_name(path), modules))
+            }
+        };
+    }
+
+    let file = find_library(path, importing_file, options)?;
+
+    Input::from_path(&file).map_err(|e| format!("{}: {}", file.display(), e))
+}
+
+/// Directories searched for libraries, in order of priority
+pub fn search_roots(importing_file: &Path, options: &Options) -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    let mut directory = importing_file.to_owned();
+    directory.pop();
+    if directory.as_os_str().is_empty() {
+        directory.push(".");
+    }
+    roots.push(directory);
+
+    roots.extend(options.lib_paths.iter().cloned());
+
+    if let Some(sdf_path) = std::env::var_os("SDF_PATH") {
+        roots.extend(std::env::split_paths(&sdf_path).filter(|p| !p.as_os_str().is_empty()));
+    }
+
+    roots
+}
+
+/// Find the file referred to by an import path
+pub fn find_library(path: &[String], importing_file: &Path, options: &Options) -> Result<PathBuf, String> {
+    let mut relative: PathBuf = path.iter().collect();
+    relative.set_extension("sdf");
+
+    let roots = search_roots(importing_file, options);
+
+    for root in &roots {
+        let candidate = root.join(&relative);
+
+        if candidate.is_file() {
+            return Ok(candidate);
+        }
+    }
+
+    let searched = roots.iter()
+        .map(|root| format!("\t{}", root.display()))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Err(format!("Library '{}' ({}) was not found. Searched in:\n{}", module_name(path), relative.display(), searched))
+}
+
+/// How a module is referred to in diagnostics (`shapes::primitives`)
+pub fn module_name(path: &[String]) -> String {
+    path.join("::")
+}
+
+/// Prepended to the GLSL names of a module's items (`shapes__primitives`)
+pub fn module_prefix(path: &[String]) -> String {
+    path.join("__")
+}
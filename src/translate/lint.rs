@@ -0,0 +1,1157 @@
+use crate::parse::ast::*;
+use crate::parse::context::{Context, ShaderType};
+use crate::parse::types::{Scalar, Type};
+use crate::parse::glsl;
+use crate::parse::Input;
+
+use super::{flow, import, template, Options};
+
+use std::collections::{HashMap, HashSet};
+
+// Lints
+//
+// Run on each file after it was validated. Unlike errors, lints point out code that is valid but
+// probably wrong. Each lint has a name and a default level, which can be changed on the command line
+// (`-A name`, `-W name`, `--deny name`) or with attributes: `@allow(name)` on an item, or `@allow(name);`
+// for the whole file. Attributes take precedence over the command line, `warnings` refers to
+// every lint that would otherwise warn, and a group name (like `performance`) to the lints of that group.
+
+pub struct Lint {
+    pub name: &'static str,
+    pub default: LintLevel,
+    pub description: &'static str,
+    pub group: Option<&'static str>,
+}
+
+const fn lint(name: &'static str, default: LintLevel, description: &'static str) -> Lint {
+    Lint { name, default, description, group: None }
+}
+
+/// GPU-performance hazards. These come with a suggested rewrite
+const fn performance(name: &'static str, description: &'static str) -> Lint {
+    Lint { name, default: LintLevel::Warn, description, group: Some(PERFORMANCE) }
+}
+
+pub const LINTS: &[Lint] = &[
+    lint("unused_variables", LintLevel::Warn, "local variables and parameters that are never used"),
+    lint("unused_functions", LintLevel::Warn, "private functions that are never called"),
+    lint("unused_imports", LintLevel::Warn, "imported modules that are never referenced"),
+    lint("unused_uniforms", LintLevel::Warn, "uniforms and textures that are never read"),
+    lint("shadowed_variables", LintLevel::Warn, "variables that hide a variable of an enclosing scope"),
+    lint("implicit_narrowing", LintLevel::Warn, "implicit conversions that may change a value"),
+    lint("self_assignment", LintLevel::Warn, "assigning a variable to itself"),
+    lint("constant_condition", LintLevel::Warn, "'if' and 'while' conditions that never change"),
+    lint("float_equality", LintLevel::Warn, "comparing floating-point values with '==' or '!='"),
+    lint("empty_features", LintLevel::Warn, "'features' blocks without any features"),
+    lint("unreachable_code", LintLevel::Warn, "statements after 'return', 'break', or 'continue'"),
+
+    performance("pow_constant_exponent", "'pow' with a small constant exponent, which multiplication or 'sqrt' computes faster"),
+    performance("redundant_normalize", "'normalize' of a value that is already normalized"),
+    performance("unbounded_loop", "'while' loops in fragment shaders whose condition cannot end them ('while true', or a condition on nothing the loop modifies)"),
+    performance("divergent_texture", "texture lookups with implicit derivatives inside branches that differ between pixels"),
+    performance("dynamic_array_index", "large local arrays indexed by a non-constant value, which spill to slow memory"),
+    performance("repeated_evaluation", "functions returning 'float' (such as 'map') evaluated at more than 4 offsets from one point by one function"),
+];
+
+/// Refers to every lint that is at the warning level
+pub const WARNINGS: &str = "warnings";
+pub const PERFORMANCE: &str = "performance";
+
+/// `repeated_evaluation` allows this many samples around a point, enough for tetrahedral normal estimation
+const MAX_EVALUATIONS: usize = 4;
+/// `dynamic_array_index` allows arrays up to this size
+const MAX_INDEXED_ARRAY: u32 = 16;
+
+/// Lookups that compute the level of detail from derivatives, which are undefined in divergent control flow
+const IMPLICIT_LOD_LOOKUPS: &[&str] = &["texture", "textureOffset", "textureProj", "textureProjOffset"];
+
+pub fn find(name: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|lint| lint.name == name)
+}
+
+/// Whether `name` can be given to `-A`/`-W`/`--deny` or a lint attribute
+pub fn is_lint(name: &str) -> bool {
+    name == WARNINGS || name == PERFORMANCE || find(name).is_some()
+}
+
+/// Lint levels set by the attributes of one file
+#[derive(Debug, Default)]
+pub struct Attributes {
+    /// `@allow(...);` and such, in order
+    file: Vec<(LintLevel, String)>,
+    /// Span of an item -> Levels set by the attributes on that item
+    items: HashMap<Span, Vec<(LintLevel, String)>>,
+}
+
+impl Attributes {
+    /// The level of a lint within the item at `item` (`None` outside of items)
+    fn level(&self, lint: &Lint, item: Option<Span>, options: &Options) -> LintLevel {
+        let item_levels = item.and_then(|span| self.items.get(&span));
+
+        options.lints.iter()
+            .chain(self.file.iter())
+            .chain(item_levels.into_iter().flatten())
+            .fold(lint.default, |level, (new, name)| {
+                if name == lint.name || Some(name.as_str()) == lint.group || (name == WARNINGS && level == LintLevel::Warn) {
+                    *new
+                } else {
+                    level
+                }
+            })
+    }
+}
+
+/// Remove lint attributes from the AST, recording what they apply to
+pub fn resolve_attributes(items: &mut Vec<Item>, input: &Input) -> Result<Attributes, String> {
+    let mut attributes = Attributes::default();
+    let mut resolved = Vec::new();
+
+    for item in items.drain(..) {
+        if let Item::Lint { level, lints, item: None, span } = item {
+            check_names(&lints, span, input)?;
+            attributes.file.extend(lints.into_iter().map(|lint| (level, lint)));
+        } else {
+            resolved.push(resolve_item(item, Vec::new(), &mut attributes, input)?);
+        }
+    }
+
+    *items = resolved;
+    Ok(attributes)
+}
+
+/// Unwrap an item's attributes (`levels` holds those of enclosing items)
+fn resolve_item(item: Item, mut levels: Vec<(LintLevel, String)>, attributes: &mut Attributes, input: &Input) -> Result<Item, String> {
+    match item {
+        Item::Lint { level, lints, item: Some(item), span } => {
+            check_names(&lints, span, input)?;
+            levels.extend(lints.into_iter().map(|lint| (level, lint)));
+
+            match *item {
+                // Nothing in these is linted, so the attribute would do nothing
+                Item::Use { .. } | Item::Scene { .. } => {
+                    Err(format!("{}\nLint attributes cannot be applied to 'use' or 'scene' items", input.evaluate_span(span)))
+                }
+                item => resolve_item(item, levels, attributes, input),
+            }
+        }
+
+        // Attributes on an implementation apply to each of its functions
+        Item::Implementation { struct_name, functions } => {
+            let functions = functions.into_iter()
+                .map(|function| resolve_item(function, levels.clone(), attributes, input))
+                .collect::<Result<_, _>>()?;
+
+            Ok(Item::Implementation { struct_name, functions })
+        }
+
+        item => {
+            let span = match &item {
+                Item::Function { span, .. } | Item::Import { span, .. } | Item::Uniforms { span, .. }
+                | Item::Features { span, .. } | Item::Struct { span, .. } | Item::TypeAlias { span, .. } => Some(*span),
+                Item::Constant(constant) => Some(constant.span),
+                _ => None,
+            };
+
+            if let (Some(span), false) = (span, levels.is_empty()) {
+                attributes.items.insert(span, levels);
+            }
+
+            Ok(item)
+        }
+    }
+}
+
+fn check_names(lints: &[String], span: Span, input: &Input) -> Result<(), String> {
+    match lints.iter().find(|lint| !is_lint(lint)) {
+        Some(unknown) => Err(format!("{}\nUnknown lint '{}'", input.evaluate_span(span), unknown)),
+        None => Ok(()),
+    }
+}
+
+/// A variable declared within a function
+struct Local {
+    name: String,
+    ty: Option<Type>,
+    span: Span,
+    /// Which lint reports it if it is never used (`None` if it does not matter)
+    unused_lint: Option<&'static str>,
+    used: bool,
+    /// Declared with `const`
+    constant: bool,
+    /// Every pixel has the same value (as far as the linter can tell)
+    uniform: bool,
+    /// Holds the result of `normalize`
+    normalized: bool,
+}
+
+/// Lint a validated file
+pub fn check(ast: &AST, attributes: &Attributes, context: &mut Context, input: &Input) -> Result<(), String> {
+    let mut linter = Linter {
+        context,
+        input,
+        attributes,
+        item: None,
+        scopes: Vec::new(),
+        return_type: Type::Void,
+        referenced: HashSet::new(),
+        divergent: 0,
+        loops: 0,
+        evaluations: HashMap::new(),
+    };
+
+    for item in ast {
+        match item {
+            Item::Function { parameters, return_type, statements, span, .. } => {
+                linter.check_function(parameters, return_type, statements, *span)?;
+            }
+
+            Item::Implementation { functions, .. } => {
+                for function in functions {
+                    if let Item::Function { parameters, return_type, statements, span, .. } = function {
+                        linter.check_function(parameters, return_type, statements, *span)?;
+                    }
+                }
+            }
+
+            Item::Constant(constant) => {
+                linter.item = Some(constant.span);
+                linter.check_expression(&constant.value.expression, constant.value.span)?;
+            }
+
+            // Defaults are linted here rather than in each constructor that uses them
+            Item::Struct { fields, span, .. } => {
+                linter.item = Some(*span);
+
+                for (_field, _ty, default) in fields {
+                    if let Some(default) = default {
+                        linter.check_expression(default, *span)?;
+                    }
+                }
+            }
+
+            Item::Uniforms { uniforms, span } => {
+                linter.item = Some(*span);
+
+                for uniform in uniforms {
+                    linter.check_expression(&uniform.default.expression, uniform.default.span)?;
+                }
+            }
+
+            Item::Features { features, span } if features.is_empty() => {
+                linter.item = Some(*span);
+                linter.report("empty_features", *span, "This 'features' block is empty and can be removed".to_owned())?;
+            }
+
+            _ => {}
+        }
+
+        linter.item = None;
+    }
+
+    linter.check_unused_items(ast)
+}
+
+struct Linter<'a> {
+    context: &'a mut Context,
+    input: &'a Input,
+    attributes: &'a Attributes,
+    /// Span of the item being linted (for its attributes)
+    item: Option<Span>,
+    /// Locals of the function being linted, innermost scope last
+    scopes: Vec<Vec<Local>>,
+    return_type: Type,
+    /// GLSL names of the functions, constants, types, and globals used by the file
+    referenced: HashSet<String>,
+    /// Number of enclosing branches whose condition may differ between pixels
+    divergent: usize,
+    /// Number of enclosing loops
+    loops: usize,
+    /// (Function, point) -> Calls outside of loops by the function being linted that sample a `float`
+    /// function (like an SDF) at the point or at an offset from it (`map(p + e.xyy)`)
+    evaluations: HashMap<(String, String), usize>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, lint: &str, span: Span, message: String) -> Result<(), String> {
+        self.report_with(lint, span, message, None)
+    }
+
+    fn report_with(&mut self, lint: &str, span: Span, message: String, suggestion: Option<String>) -> Result<(), String> {
+        let lint = find(lint).unwrap();
+
+        let mut diagnostic = format!("{}\n{} [{}]", self.input.evaluate_span(span), message, lint.name);
+        if let Some(suggestion) = suggestion {
+            diagnostic.push_str(&format!("\nSuggestion: {}", suggestion));
+        }
+
+        match self.attributes.level(lint, self.item, &self.context.options) {
+            LintLevel::Allow => Ok(()),
+            LintLevel::Warn => {
+                self.context.warn(diagnostic);
+                Ok(())
+            }
+            LintLevel::Deny => Err(diagnostic),
+        }
+    }
+
+    fn check_function(&mut self, parameters: &[(Option<FuncParamQualifier>, String, TypeSpecifier)], return_type: &TypeSpecifier,
+                      statements: &[Statement], span: Span) -> Result<(), String> {
+        self.item = Some(span);
+        self.return_type = self.context.validate_return_type(return_type)?;
+        self.evaluations.clear();
+
+        self.scopes.push(Vec::new());
+        for (_qualifier, name, ty) in parameters {
+            // Methods do not choose whether to take `self`
+            let unused_lint = if name == "self" { None } else { Some("unused_variables") };
+            self.declare(name, Some(Type::from_specifier(ty)), span, unused_lint)?;
+        }
+
+        for (statement, cause) in flow::unreachable_code(statements) {
+            self.report("unreachable_code", statement, format!("Unreachable code after {}", cause))?;
+        }
+
+        self.check_block(statements)?;
+        self.pop_scope()?;
+
+        let mut evaluations: Vec<_> = self.evaluations.drain().filter(|(_, count)| *count > MAX_EVALUATIONS).collect();
+        evaluations.sort();
+
+        for ((function, point), count) in evaluations {
+            let function = self.context.source_name(&function);
+            self.report_with("repeated_evaluation", span, format!("'{}' is evaluated {} times around '{}' by this function", function, count, point), Some(format!(
+                "Estimate normals with the tetrahedron technique, which needs {} evaluations (with a small offset 'h'): \
+                'let k = vec2(1., -1.); normalize(k.xyy * {f}({p} + k.xyy * h) + k.yyx * {f}({p} + k.yyx * h) + k.yxy * {f}({p} + k.yxy * h) + k.xxx * {f}({p} + k.xxx * h))'",
+                MAX_EVALUATIONS, f = function, p = point
+            )))?;
+        }
+
+        self.item = None;
+        Ok(())
+    }
+
+    fn check_block(&mut self, statements: &[Statement]) -> Result<(), String> {
+        self.scopes.push(Vec::new());
+
+        for statement in statements {
+            self.check_statement(statement)?;
+        }
+
+        self.pop_scope()
+    }
+
+    fn check_statement(&mut self, statement: &Statement) -> Result<(), String> {
+        match statement {
+            Statement::Let { tag, ident, ty, expression, span, .. } => {
+                let ty = ty.as_ref().map(Type::from_specifier);
+
+                if let Some(assignment) = expression {
+                    self.check_expression(&assignment.expression, assignment.span)?;
+
+                    if let Some(ty) = &ty {
+                        self.check_conversion(&assignment.expression, ty, assignment.span)?;
+                    }
+                }
+
+                let unused_lint = match tag {
+                    Some(Tag { kind: TagKind::Uniform, .. }) | Some(Tag { kind: TagKind::Texture, .. }) => Some("unused_uniforms"),
+                    // Outputs are read by whatever follows the shader
+                    Some(Tag { kind: TagKind::Out, .. }) => None,
+                    None => Some("unused_variables"),
+                };
+
+                let (uniform, normalized) = match expression {
+                    Some(assignment) => (self.is_uniform(&assignment.expression), self.is_normalized(&assignment.expression)),
+                    None => (true, false),
+                };
+
+                let local = self.declare(ident, ty, *span, unused_lint)?;
+                local.uniform = uniform;
+                local.normalized = normalized;
+            }
+
+            Statement::LetConstructor { ident, constructor, .. } => {
+                // Filled-in defaults have no span, and are linted with their struct
+                for (_field, value) in constructor.fields.iter().filter(|(_field, value)| value.span != (0, 0)) {
+                    self.check_expression(&value.expression, value.span)?;
+                }
+
+                self.referenced.insert(constructor.ty.clone());
+
+                let uniform = constructor.fields.iter().all(|(_field, value)| self.is_uniform(&value.expression));
+
+                // Without fields, there is nothing to point at
+                let local = match constructor.fields.first() {
+                    Some((_field, value)) => {
+                        let span = value.span;
+                        self.declare(ident, Some(Type::Struct(constructor.ty.clone())), span, Some("unused_variables"))?
+                    }
+                    None => self.declare(ident, Some(Type::Struct(constructor.ty.clone())), (0, 0), None)?,
+                };
+                local.uniform = uniform;
+            }
+
+            Statement::LetPattern { pattern, expression, .. } => {
+                self.check_expression(&expression.expression, expression.span)?;
+                self.declare_pattern(pattern, expression.span, self.is_uniform(&expression.expression))?;
+            }
+
+            Statement::Constant(constant) => {
+                self.check_expression(&constant.value.expression, constant.value.span)?;
+                let local = self.declare(&constant.ident, Some(Type::from_specifier(&constant.ty)), constant.value.span, Some("unused_variables"))?;
+                local.constant = true;
+                local.uniform = true;
+            }
+
+            Statement::Assignment { lhs, op, expression } => {
+                self.check_expression(&expression.expression, expression.span)?;
+                self.check_expression(&lhs.expression, lhs.span)?;
+
+                if let AssignmentOperator::Assign = op {
+                    if same_place(&lhs.expression, &expression.expression) {
+                        self.report("self_assignment", lhs.span, "This assigns a variable to itself, which has no effect".to_owned())?;
+                    }
+
+                    if let Some(ty) = self.type_of(&lhs.expression) {
+                        self.check_conversion(&expression.expression, &ty, expression.span)?;
+                    }
+                }
+
+                // Writing to a field or component may denormalize a vector
+                let whole = matches!(lhs.expression, Expression::Identifier(_)) && matches!(op, AssignmentOperator::Assign);
+                let (uniform, normalized) = (self.is_uniform(&expression.expression), whole && self.is_normalized(&expression.expression));
+
+                if let Some(local) = super::assigned_variable(&lhs.expression).and_then(|name| self.local_mut(name)) {
+                    local.uniform &= uniform;
+                    local.normalized = normalized;
+                }
+            }
+
+            Statement::Return { expression: Some(expression), .. } => {
+                self.check_expression(&expression.expression, expression.span)?;
+
+                let return_type = self.return_type.clone();
+                self.check_conversion(&expression.expression, &return_type, expression.span)?;
+            }
+
+            Statement::For { loop_var, from, to, block } => {
+                self.check_expression(&from.expression, from.span)?;
+                self.check_expression(&to.expression, to.span)?;
+
+                let uniform = self.is_uniform(&from.expression) && self.is_uniform(&to.expression);
+
+                self.scopes.push(Vec::new());
+                self.declare(loop_var, Some(Type::INT), from.span, Some("unused_variables"))?.uniform = uniform;
+
+                self.loops += 1;
+                self.check_block(block)?;
+                self.loops -= 1;
+
+                self.pop_scope()?;
+            }
+
+            Statement::While { condition, block, do_while } => {
+                self.check_expression(&condition.expression, condition.span)?;
+
+                // `while true { ... }`, `do { ... } while true;`, and `do { ... } while false;` are deliberate
+                let idiomatic = matches!(condition.expression, Expression::Literal(Literal::Bool(value)) if value || *do_while);
+                if !idiomatic {
+                    self.check_condition(&condition.expression, condition.span)?;
+                }
+
+                if self.context.shader_type == ShaderType::Fragment {
+                    self.check_loop_bound(&condition.expression, block, condition.span)?;
+                }
+
+                let divergent = !self.is_uniform(&condition.expression) as usize;
+
+                self.loops += 1;
+                self.divergent += divergent;
+                self.check_block(block)?;
+                self.divergent -= divergent;
+                self.loops -= 1;
+            }
+
+            Statement::Expression { expression, span } => self.check_expression(expression, *span)?,
+
+            Statement::Block { statements, .. } => self.check_block(statements)?,
+
+            Statement::Return { expression: None, .. } | Statement::Continue(_) | Statement::Break(_) => {}
+
+            Statement::Cfg { .. } | Statement::IfConst { .. } => unreachable!("cfg statements are resolved before validation"),
+        }
+
+        Ok(())
+    }
+
+    /// `span` locates the statement or expression that contains `expression`
+    fn check_expression(&mut self, expression: &Expression, span: Span) -> Result<(), String> {
+        match expression {
+            Expression::Literal(_) => {}
+
+            Expression::Identifier(name) => self.use_name(name),
+
+            Expression::Parenthesized(expr) => self.check_expression(expr, span)?,
+
+            Expression::ArrayConstructor { expressions, .. } | Expression::Tuple { expressions, .. } => {
+                for expr in expressions {
+                    self.check_expression(expr, span)?;
+                }
+            }
+
+            Expression::Binary { lhs, operator, rhs, .. } => {
+                self.check_expression(lhs, span)?;
+
+                match (operator, rhs.as_ref()) {
+                    // The right side of a cast is a type
+                    (BinaryOperator::Cast, Expression::Identifier(ty)) => { self.referenced.insert(ty.clone()); }
+                    _ => self.check_expression(rhs, span)?,
+                }
+
+                if let BinaryOperator::EqualTo | BinaryOperator::NotEqualTo = operator {
+                    let floating = |ty: Option<Type>| matches!(ty, Some(Type::Scalar(Scalar::Float)) | Some(Type::Scalar(Scalar::Double)));
+
+                    if floating(self.type_of(lhs)) || floating(self.type_of(rhs)) {
+                        self.report("float_equality", span, format!(
+                            "Floating-point values are rarely exactly equal, so '{}' is unreliable (compare 'abs(a - b) < epsilon' instead)",
+                            crate::parse::glsl::operators::symbol(operator)
+                        ))?;
+                    }
+                }
+            }
+
+            Expression::Unary { operator, expr, .. } => {
+                self.check_expression(expr, span)?;
+
+                if let UnaryOperator::Index(index) = operator {
+                    self.check_expression(index, span)?;
+                    self.check_index(expr, index, span)?;
+                }
+            }
+
+            Expression::FunctionApply(apply) => {
+                self.referenced.insert(apply.name.clone());
+
+                for expr in &apply.parameters {
+                    self.check_expression(expr, span)?;
+                }
+            }
+
+            Expression::FunctionCall(call) => self.check_call(call, span)?,
+
+            Expression::Member(member) => {
+                for item in &member.path {
+                    match item {
+                        // Only the first identifier is a variable (the rest are fields)
+                        IdentOrFunction::Ident(name) => {
+                            if std::ptr::eq(item, &member.path[0]) {
+                                self.use_name(name);
+                            }
+                        }
+
+                        // Method calls were given their receiver as the first argument
+                        IdentOrFunction::Function(call) => self.check_call(call, span)?,
+                    }
+                }
+            }
+
+            Expression::If { expression, if_block, else_block, else_if_block, .. } => {
+                self.check_expression(expression, span)?;
+                self.check_condition(expression, span)?;
+
+                // Which branch runs may differ between pixels
+                let divergent = !self.is_uniform(expression) as usize;
+                self.divergent += divergent;
+
+                self.check_block(if_block)?;
+
+                if let Some(block) = else_block {
+                    self.check_block(block)?;
+                }
+
+                if let Some(else_if) = else_if_block {
+                    self.check_expression(else_if, span)?;
+                }
+
+                self.divergent -= divergent;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_call(&mut self, call: &FunctionCall, span: Span) -> Result<(), String> {
+        self.referenced.insert(call.name.clone());
+
+        for expr in &call.parameters {
+            self.check_expression(expr, span)?;
+        }
+
+        match (call.name.as_str(), call.parameters.as_slice()) {
+            ("pow", [base, exponent]) => self.check_pow(base, exponent, span)?,
+
+            ("normalize", [value]) if self.is_normalized(value) => {
+                self.report_with("redundant_normalize", span, "This value is already normalized".to_owned(), Some(
+                    format!("Use '{}' directly", self.source_expression(value))
+                ))?;
+            }
+
+            (name, _) if IMPLICIT_LOD_LOOKUPS.contains(&name) && self.divergent > 0 && self.context.shader_type == ShaderType::Fragment => {
+                self.report_with("divergent_texture", span, format!(
+                    "'{}' is called in a branch that may differ between neighboring pixels, where its implicit derivatives are undefined", name
+                ), Some(format!("Sample before branching, or use 'textureLod' or 'textureGrad' instead of '{}'", name)))?;
+            }
+
+            // Libraries (including std) sample helpers like hashes many times on purpose
+            (name, [point, ..]) if self.loops == 0 && call.ty == Some(Type::FLOAT) && !glsl::functions::is_builtin(name) && !self.in_library() => {
+                if let Some(point) = sample_point(point) {
+                    *self.evaluations.entry((name.to_owned(), point.to_owned())).or_insert(0) += 1;
+                }
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// `pow(x, 2.)` and such can be computed without `exp2(y * log2(x))`
+    fn check_pow(&mut self, base: &Expression, exponent: &Expression, span: Span) -> Result<(), String> {
+        let exponent = match exponent {
+            Expression::Literal(Literal::Float(value)) => *value as f64,
+            Expression::Literal(Literal::Double(value)) => *value,
+            Expression::Literal(Literal::Int(value)) => *value as f64,
+            _ => return Ok(()),
+        };
+
+        let x = self.source_expression(base);
+        if exponent == 0.5 {
+            return self.report_with("pow_constant_exponent", span, "'pow' with the exponent 0.5 is slower than 'sqrt'".to_owned(), Some(
+                format!("'sqrt({})'", x)
+            ));
+        }
+
+        let power = match exponent {
+            e if e == 1. || e == 2. || e == 3. || e == 4. => e as usize,
+            _ => return Ok(()),
+        };
+
+        // Repeating anything but a variable would repeat its computation, so the rewrite needs a new variable
+        let message = format!("'pow' with the exponent {} is slower than multiplying", exponent);
+        match base {
+            _ if power == 1 => {
+                let x = if matches!(base, Expression::Binary { .. }) { format!("({})", x) } else { x };
+                self.report_with("pow_constant_exponent", span, "'pow' with the exponent 1 returns its base".to_owned(), Some(format!("'{}'", x)))
+            }
+
+            Expression::Identifier(_) | Expression::Member(_) => {
+                self.report_with("pow_constant_exponent", span, message, Some(format!("'{}'", vec![x; power].join(" * "))))
+            }
+
+            _ => self.report("pow_constant_exponent", span, format!("{} (store the base in a variable, and multiply it by itself)", message)),
+        }
+    }
+
+    /// A loop whose condition cannot become false only ends through `break` or `return`, which may never happen for some pixel
+    fn check_loop_bound(&mut self, condition: &Expression, block: &[Statement], span: Span) -> Result<(), String> {
+        let message = if let Expression::Literal(Literal::Bool(true)) = condition {
+            "This loop only ends through 'break' or 'return', so a single pixel can stall the GPU"
+        } else {
+            let mut modified = HashSet::new();
+            self.modified(block, &mut modified);
+
+            // Loops whose condition is a constant `false` are reported by `constant_condition`
+            let read = super::reach::expression_references(condition).names;
+            if is_constant(condition) || read.iter().any(|name| modified.contains(name)) {
+                return Ok(());
+            }
+
+            "This loop does not modify anything its condition reads, so it only ends through 'break' or 'return' and a single pixel can stall the GPU"
+        };
+
+        self.report_with("unbounded_loop", span, message.to_owned(), Some(
+            "Bound the loop, e.g. 'for i in 0 .. MAX_STEPS { if !(condition) { break; } ... }'".to_owned()
+        ))
+    }
+
+    /// Variables that statements may modify (assignments, and `out`/`inout` arguments)
+    fn modified(&self, statements: &[Statement], found: &mut HashSet<String>) {
+        for statement in statements {
+            match statement {
+                Statement::Assignment { lhs, expression, .. } => {
+                    found.extend(super::assigned_variable(&lhs.expression).map(str::to_owned));
+                    self.modified_by(&expression.expression, found);
+                }
+
+                Statement::Let { expression: Some(value), .. } | Statement::Return { expression: Some(value), .. } | Statement::LetPattern { expression: value, .. } => {
+                    self.modified_by(&value.expression, found);
+                }
+
+                Statement::LetConstructor { constructor, .. } => {
+                    for (_field, value) in &constructor.fields {
+                        self.modified_by(&value.expression, found);
+                    }
+                }
+
+                Statement::For { from, to, block, .. } => {
+                    self.modified_by(&from.expression, found);
+                    self.modified_by(&to.expression, found);
+                    self.modified(block, found);
+                }
+
+                Statement::While { condition, block, .. } => {
+                    self.modified_by(&condition.expression, found);
+                    self.modified(block, found);
+                }
+
+                Statement::Expression { expression, .. } => self.modified_by(expression, found),
+                Statement::Block { statements, .. } => self.modified(statements, found),
+
+                _ => {}
+            }
+        }
+    }
+
+    fn modified_by(&self, expression: &Expression, found: &mut HashSet<String>) {
+        match expression {
+            Expression::FunctionCall(call) => self.modified_by_call(&call.name, &call.parameters, found),
+            Expression::FunctionApply(apply) => self.modified_by_call(&apply.name, &apply.parameters, found),
+
+            Expression::Member(member) => {
+                for item in &member.path {
+                    if let IdentOrFunction::Function(call) = item {
+                        self.modified_by_call(&call.name, &call.parameters, found);
+                    }
+                }
+            }
+
+            Expression::Binary { lhs, rhs, .. } => {
+                self.modified_by(lhs, found);
+                self.modified_by(rhs, found);
+            }
+
+            Expression::Unary { operator, expr, .. } => {
+                if let UnaryOperator::Index(index) = operator {
+                    self.modified_by(index, found);
+                }
+
+                self.modified_by(expr, found);
+            }
+
+            Expression::Parenthesized(expr) => self.modified_by(expr, found),
+
+            Expression::ArrayConstructor { expressions, .. } | Expression::Tuple { expressions, .. } => {
+                for expr in expressions {
+                    self.modified_by(expr, found);
+                }
+            }
+
+            Expression::If { expression, if_block, else_block, else_if_block, .. } => {
+                self.modified_by(expression, found);
+                self.modified(if_block, found);
+
+                if let Some(block) = else_block {
+                    self.modified(block, found);
+                }
+
+                if let Some(else_if) = else_if_block {
+                    self.modified_by(else_if, found);
+                }
+            }
+
+            Expression::Literal(_) | Expression::Identifier(_) => {}
+        }
+    }
+
+    fn modified_by_call(&self, name: &str, arguments: &[Expression], found: &mut HashSet<String>) {
+        for (index, argument) in arguments.iter().enumerate() {
+            if self.context.parameter_qualifier(name, index) != FuncParamQualifier::In {
+                found.extend(super::assigned_variable(argument).map(str::to_owned));
+            }
+
+            self.modified_by(argument, found);
+        }
+    }
+
+    /// Indexing a large local array by a varying value forces it out of registers
+    fn check_index(&mut self, array: &Expression, index: &Expression, span: Span) -> Result<(), String> {
+        let local = match array {
+            Expression::Identifier(name) => self.local(name),
+            _ => None,
+        };
+
+        if let Some(Local { ty: Some(Type::Array(_, size)), constant: false, name, .. }) = local {
+            if *size > MAX_INDEXED_ARRAY && !is_constant(index) {
+                let (name, size) = (name.clone(), *size);
+
+                return self.report_with("dynamic_array_index", span, format!(
+                    "'{}' holds {} elements and is indexed by a non-constant value, so it is likely stored in slow local memory", name, size
+                ), Some(format!(
+                    "Make '{}' a 'const' array or a uniform, or keep it at most {} elements long", name, MAX_INDEXED_ARRAY
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_condition(&mut self, condition: &Expression, span: Span) -> Result<(), String> {
+        if !is_constant(condition) {
+            return Ok(());
+        }
+
+        let message = match condition {
+            Expression::Literal(Literal::Bool(value)) => format!("This condition is always {}", value),
+            _ => "This condition only depends on literals, so it never changes".to_owned(),
+        };
+
+        self.report("constant_condition", span, format!("{} (use 'if const' or '@cfg' to select code at compile time)", message))
+    }
+
+    /// Implicit conversions allowed by GLSL that may not preserve the value
+    fn check_conversion(&mut self, expression: &Expression, to: &Type, span: Span) -> Result<(), String> {
+        let from = match self.type_of(expression) {
+            Some(from) => from,
+            None => return Ok(()),
+        };
+
+        let same_shape = from.size().is_some() && from.size() == to.size();
+        let non_negative = matches!(expression, Expression::Literal(Literal::Int(value)) if *value >= 0);
+
+        if same_shape && !non_negative && from.scalar() == Some(Scalar::Int) && to.scalar() == Some(Scalar::UInt) {
+            return self.report("implicit_narrowing", span, format!(
+                "Implicitly converting '{}' to '{}' turns negative values into large positive ones (convert explicitly if this is intended)", from, to
+            ));
+        }
+
+        // Floats have 24 bits of precision
+        if let (Expression::Literal(Literal::Int(value)), Type::Scalar(Scalar::Float)) = (expression, to) {
+            if value.unsigned_abs() > 1 << 24 {
+                return self.report("implicit_narrowing", span, format!("The integer {} cannot be represented exactly as a 'float'", value));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn declare(&mut self, name: &str, ty: Option<Type>, span: Span, unused_lint: Option<&'static str>) -> Result<&mut Local, String> {
+        if !name.starts_with('_') && self.scopes.iter().flatten().any(|local| local.name == name) {
+            self.report("shadowed_variables", span, format!("'{}' shadows a variable of an enclosing scope", name))?;
+        }
+
+        let scope = self.scopes.last_mut().unwrap();
+        scope.push(Local {
+            name: name.to_owned(),
+            ty,
+            span,
+            unused_lint,
+            used: false,
+            constant: false,
+            uniform: false,
+            normalized: false,
+        });
+
+        Ok(scope.last_mut().unwrap())
+    }
+
+    /// The innermost local called `name`
+    fn local(&self, name: &str) -> Option<&Local> {
+        self.scopes.iter().rev().flat_map(|scope| scope.iter().rev()).find(|local| local.name == name)
+    }
+
+    fn local_mut(&mut self, name: &str) -> Option<&mut Local> {
+        self.scopes.iter_mut().rev().flat_map(|scope| scope.iter_mut().rev()).find(|local| local.name == name)
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern, span: Span, uniform: bool) -> Result<(), String> {
+        match pattern {
+            Pattern::Binding { ident, ty, .. } => {
+                if ident != "_" {
+                    self.declare(ident, ty.as_ref().map(Type::from_specifier), span, Some("unused_variables"))?.uniform = uniform;
+                }
+            }
+
+            Pattern::Tuple(patterns) | Pattern::Vector(patterns) => {
+                for pattern in patterns {
+                    self.declare_pattern(pattern, span, uniform)?;
+                }
+            }
+
+            Pattern::Struct { fields, .. } => {
+                for (_field, pattern) in fields {
+                    self.declare_pattern(pattern, span, uniform)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn pop_scope(&mut self) -> Result<(), String> {
+        for local in self.scopes.pop().unwrap() {
+            if let (false, Some(lint)) = (local.used || local.name.starts_with('_'), local.unused_lint) {
+                self.report(lint, local.span, format!("'{}' is never used (prefix it with '_' if this is intended)", local.name))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn use_name(&mut self, name: &str) {
+        match self.local_mut(name) {
+            Some(local) => local.used = true,
+            None => { self.referenced.insert(name.to_owned()); }
+        }
+    }
+
+    fn type_of(&self, expression: &Expression) -> Option<Type> {
+        match expression {
+            Expression::Literal(literal) => Some(match literal {
+                Literal::Float(_) => Type::FLOAT,
+                Literal::Double(_) => Type::Scalar(Scalar::Double),
+                Literal::Int(_) => Type::INT,
+                Literal::UInt(_) => Type::UINT,
+                Literal::Bool(_) => Type::BOOL,
+            }),
+
+            Expression::Parenthesized(expr) => self.type_of(expr),
+
+            Expression::Identifier(name) => {
+                match self.local(name) {
+                    Some(local) => local.ty.clone(),
+                    None => self.context.scopes.var_type(name).ok().cloned(),
+                }
+            }
+
+            expr => self.context.expression_type(expr).ok(),
+        }
+    }
+
+    /// Whether every pixel computes the same value. Globals other than built-in inputs (uniforms, constants, etc.) are the same everywhere
+    fn is_uniform(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::Literal(_) => true,
+
+            Expression::Identifier(name) => match self.local(name) {
+                Some(local) => local.uniform,
+                None => !name.starts_with("gl_"),
+            },
+
+            Expression::Parenthesized(expr) => self.is_uniform(expr),
+
+            Expression::ArrayConstructor { expressions, .. } | Expression::Tuple { expressions, .. } => {
+                expressions.iter().all(|expr| self.is_uniform(expr))
+            }
+
+            // The right side of a cast is a type
+            Expression::Binary { lhs, operator: BinaryOperator::Cast, .. } => self.is_uniform(lhs),
+            Expression::Binary { lhs, rhs, .. } => self.is_uniform(lhs) && self.is_uniform(rhs),
+
+            Expression::Unary { operator: UnaryOperator::Index(index), expr, .. } => self.is_uniform(expr) && self.is_uniform(index),
+            Expression::Unary { expr, .. } => self.is_uniform(expr),
+
+            Expression::FunctionApply(FunctionApply { parameters, .. }) | Expression::FunctionCall(FunctionCall { parameters, .. }) => {
+                parameters.iter().all(|expr| self.is_uniform(expr))
+            }
+
+            Expression::Member(member) => member.path.iter().enumerate().all(|(i, item)| match item {
+                IdentOrFunction::Ident(name) => i > 0 || self.is_uniform(&Expression::Identifier(name.clone())),
+                IdentOrFunction::Function(call) => call.parameters.iter().all(|expr| self.is_uniform(expr)),
+            }),
+
+            Expression::If { .. } => false,
+        }
+    }
+
+    /// Print an expression with the names it was written with, rather than mangled GLSL names
+    fn source_expression(&self, expression: &Expression) -> String {
+        let mut expression = expression.clone();
+        self.unmangle(&mut expression);
+
+        template::translate_expression(&expression)
+    }
+
+    fn unmangle(&self, expression: &mut Expression) {
+        match expression {
+            Expression::Identifier(name) => *name = self.context.source_name(name),
+
+            Expression::FunctionCall(call) => {
+                call.name = self.context.source_name(&call.name);
+                call.parameters.iter_mut().for_each(|expr| self.unmangle(expr));
+            }
+
+            Expression::FunctionApply(apply) => {
+                apply.name = self.context.source_name(&apply.name);
+                apply.parameters.iter_mut().for_each(|expr| self.unmangle(expr));
+            }
+
+            Expression::Member(member) => {
+                let mut path = Vec::new();
+
+                for item in member.path.drain(..) {
+                    match item {
+                        // Methods (`__Struct__method(receiver, ...)`) are printed as `receiver.method(...)`
+                        IdentOrFunction::Function(mut call) if call.name.starts_with("__") && !call.parameters.is_empty() => {
+                            let mut receiver = call.parameters.remove(0);
+                            self.unmangle(&mut receiver);
+                            call.parameters.iter_mut().for_each(|expr| self.unmangle(expr));
+                            call.name = call.name.rsplit("__").next().unwrap_or_default().to_owned();
+
+                            path.push(IdentOrFunction::Ident(template::translate_expression(&receiver)));
+                            path.push(IdentOrFunction::Function(call));
+                        }
+
+                        IdentOrFunction::Function(mut call) => {
+                            call.name = self.context.source_name(&call.name);
+                            call.parameters.iter_mut().for_each(|expr| self.unmangle(expr));
+                            path.push(IdentOrFunction::Function(call));
+                        }
+
+                        IdentOrFunction::Ident(name) if path.is_empty() => path.push(IdentOrFunction::Ident(self.context.source_name(&name))),
+                        ident => path.push(ident),
+                    }
+                }
+
+                member.path = path;
+            }
+
+            Expression::Binary { lhs, rhs, .. } => {
+                self.unmangle(lhs);
+                self.unmangle(rhs);
+            }
+
+            Expression::Unary { operator, expr, .. } => {
+                if let UnaryOperator::Index(index) = operator {
+                    self.unmangle(index);
+                }
+
+                self.unmangle(expr);
+            }
+
+            Expression::Parenthesized(expr) => self.unmangle(expr),
+
+            Expression::ArrayConstructor { expressions, .. } | Expression::Tuple { expressions, .. } => {
+                expressions.iter_mut().for_each(|expr| self.unmangle(expr));
+            }
+
+            // Only blocks would contain further names, which suggestions do not print
+            Expression::If { expression, .. } => self.unmangle(expression),
+
+            Expression::Literal(_) => {}
+        }
+    }
+
+    /// Whether a value is known to have a length of 1
+    fn is_normalized(&self, expression: &Expression) -> bool {
+        match expression {
+            Expression::FunctionCall(call) => call.name == "normalize",
+            Expression::Identifier(name) => self.local(name).map_or(false, |local| local.normalized),
+            Expression::Parenthesized(expr) => self.is_normalized(expr),
+            _ => false,
+        }
+    }
+
+    /// Whether the file being linted is a library (imported, or compiled on its own)
+    fn in_library(&self) -> bool {
+        self.context.shader_type == ShaderType::Library || !self.context.module_prefix().is_empty()
+    }
+
+    /// Items that nothing in the file refers to
+    fn check_unused_items(&mut self, ast: &AST) -> Result<(), String> {
+        let prefix = match self.context.module_prefix() {
+            "" => String::new(),
+            prefix => format!("{}__", prefix),
+        };
+        let library = self.in_library();
+
+        for item in ast {
+            match item {
+                // Public functions of libraries are used by importers
+                Item::Function { name, public, span, .. } if !self.referenced.contains(name) && name != "main" && !(library && *public) => {
+                    self.item = Some(*span);
+
+                    let name = name.strip_prefix(&prefix).unwrap_or(name);
+                    self.report("unused_functions", *span, format!("Function '{}' is never called", name))?;
+                }
+
+                Item::Import { path, alias, span } => {
+                    let alias = alias.as_ref().unwrap_or(path.last().unwrap());
+                    let module_prefix = format!("{}__", self.context.imported_prefix(alias)?);
+                    let method_prefix = format!("__{}", module_prefix);
+
+                    let used = self.referenced.iter().any(|name| name.starts_with(&module_prefix) || name.starts_with(&method_prefix));
+                    if !used {
+                        self.item = Some(*span);
+                        self.report("unused_imports", *span, format!("Nothing from '{}' is used", import::module_name(path)))?;
+                    }
+                }
+
+                Item::Uniforms { uniforms, span } => {
+                    self.item = Some(*span);
+
+                    for uniform in uniforms {
+                        if !self.referenced.contains(&uniform.ident) {
+                            self.report("unused_uniforms", uniform.default.span, format!("Uniform '{}' is never used", uniform.ident))?;
+                        }
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        self.item = None;
+        Ok(())
+    }
+}
+
+/// The point an argument samples at: `p`, or `p` offset by something (`p + e.xyy`, `p - h`)
+fn sample_point(argument: &Expression) -> Option<&str> {
+    match argument {
+        Expression::Identifier(point) => Some(point),
+        Expression::Parenthesized(expr) => sample_point(expr),
+        Expression::Binary { lhs, operator: BinaryOperator::Plus | BinaryOperator::Minus, rhs, .. } => match (lhs.as_ref(), rhs.as_ref()) {
+            (Expression::Identifier(point), _) | (_, Expression::Identifier(point)) => Some(point),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether an expression only involves literals
+fn is_constant(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(_) => true,
+        Expression::Parenthesized(expr) => is_constant(expr),
+        Expression::Unary { operator: UnaryOperator::Negate, expr, .. } | Expression::Unary { operator: UnaryOperator::Not, expr, .. } => is_constant(expr),
+        Expression::Binary { lhs, operator: BinaryOperator::Cast, .. } => is_constant(lhs),
+        Expression::Binary { lhs, rhs, .. } => is_constant(lhs) && is_constant(rhs),
+        _ => false,
+    }
+}
+
+/// Whether both expressions name the same variable, field, or element
+fn same_place(a: &Expression, b: &Expression) -> bool {
+    match (a, b) {
+        (Expression::Parenthesized(a), b) | (b, Expression::Parenthesized(a)) => same_place(a, b),
+
+        (Expression::Identifier(a), Expression::Identifier(b)) => a == b,
+
+        (Expression::Member(a), Expression::Member(b)) => {
+            a.path.len() == b.path.len() && a.path.iter().zip(&b.path).all(|pair| match pair {
+                (IdentOrFunction::Ident(a), IdentOrFunction::Ident(b)) => a == b,
+                _ => false,
+            })
+        }
+
+        (Expression::Unary { operator: UnaryOperator::Index(a_index), expr: a, .. },
+         Expression::Unary { operator: UnaryOperator::Index(b_index), expr: b, .. }) => {
+            same_place(a, b) && match (a_index.as_ref(), b_index.as_ref()) {
+                (Expression::Literal(Literal::Int(a)), Expression::Literal(Literal::Int(b))) => a == b,
+                (a, b) => same_place(a, b),
+            }
+        }
+
+        _ => false,
+    }
+}
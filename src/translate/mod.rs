@@ -1,13 +1,73 @@
 pub mod template;
+pub mod cfg;
+pub mod import;
+pub mod reflect;
+pub mod lint;
+mod flow;
+mod order;
+mod reach;
 
 use crate::parse::ast::*;
-use crate::parse::context::{Context, ScopeType};
+use crate::parse::context::{Context, ItemKind, Mutability, ScopeType, UniformMeta};
 use crate::parse::glsl;
+use crate::parse::types::{tuple_string, Scalar, Type};
 use crate::parse::Input;
 
-pub fn validate(ast: &mut AST, input: &Input) -> Result<Context, String> {
-    let mut context = Context::new(&input.shader_type);
+use std::collections::HashSet;
+
+/// Settings supplied by whoever drives the compilation (compiler arguments, runtime, etc.)
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// `-D key=value` pairs. Used to evaluate `@cfg(...)` and `if const`
+    pub defines: Vec<(String, String)>,
+    /// `--lib-path DIR` directories searched for imported libraries (before `SDF_PATH`)
+    pub lib_paths: Vec<std::path::PathBuf>,
+    /// `--glsl-version N` GLSL version to compile for (`DEFAULT_GLSL_VERSION` if unset)
+    pub glsl_version: Option<u32>,
+    /// `-A`, `-W`, and `--deny` lint levels, in order (see `lint::LINTS`)
+    pub lints: Vec<(LintLevel, String)>,
+    /// `--edition YEAR` language edition (`DEFAULT_EDITION` if unset)
+    pub edition: Option<u32>,
+}
+
+pub const DEFAULT_GLSL_VERSION: u32 = 450;
+
+/// Versions that can be targeted (explicit uniform locations require 4.30)
+pub const GLSL_VERSIONS: &[u32] = &[430, 440, 450, 460];
+
+pub const DEFAULT_EDITION: u32 = 2026;
+
+/// Editions that can be selected. Files written before `let mut` existed can compile with 2025
+pub const EDITIONS: &[u32] = &[2025, 2026];
+
+/// From this edition on, `let` bindings cannot be assigned to unless declared `let mut`
+pub const IMMUTABLE_LET_EDITION: u32 = 2026;
+
+impl Options {
+    pub fn glsl_version(&self) -> u32 {
+        self.glsl_version.unwrap_or(DEFAULT_GLSL_VERSION)
+    }
+
+    pub fn edition(&self) -> u32 {
+        self.edition.unwrap_or(DEFAULT_EDITION)
+    }
+}
+
+pub fn validate(ast: &mut AST, input: &Input, options: &Options) -> Result<Context, String> {
+    let mut context = Context::new(&input.shader_type, options.clone());
+
+    let name = input.path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    context.module_graph.push(canonical_path(&input.path), &name)?;
     validate_ast(ast, input, &mut context)?;
+    context.module_graph.pop(None);
+    context.assign_uniform_locations();
+
+    // Each library is emitted once, before anything that may depend on it
+    let libraries = std::mem::take(&mut context.module_graph.libraries);
+    ast.splice(0..0, libraries.into_iter().flatten());
+
+    // Only what the entry point needs is emitted
+    reach::eliminate_dead_code(ast, &mut context);
 
     Ok(context)
 }
@@ -21,10 +81,30 @@ pub fn validate(ast: &mut AST, input: &Input) -> Result<Context, String> {
 //
 // Note that nested items are translated recursively (for bottom-up type analysis like expressions)
 pub fn validate_ast(ast: &mut AST, input: &Input, context: &mut Context) -> Result<(), String> {
-    let mut imports = Vec::new();
-    
-    for (index, item) in ast.iter_mut().enumerate() {
-        match item {
+    // Disabled code is dropped before anything is validated
+    cfg::resolve_items(ast, context);
+    let attributes = lint::resolve_attributes(ast, input)?;
+    order::sort_items(ast, input)?;
+
+    // Generated tuple structs must be declared before the item that first uses them
+    let mut tuple_structs = Vec::new();
+
+    // Functions come last, and every signature is declared before any body is validated
+    let first_function = ast.iter().position(|item| matches!(item, Item::Function { .. } | Item::Implementation { .. }));
+
+    for index in 0..ast.len() {
+        if Some(index) == first_function {
+            for item in ast[index..].iter_mut() {
+                declare_signature(item, context)?;
+            }
+
+            let generated = context.take_tuple_structs();
+            if !generated.is_empty() {
+                tuple_structs.push((index, generated));
+            }
+        }
+
+        match &mut ast[index] {
             Item::Features { features, span } => {
                 let span = input.evaluate_span(*span);
 
@@ -37,37 +117,101 @@ pub fn validate_ast(ast: &mut AST, input: &Input, context: &mut Context) -> Resu
                 }
             }
 
-            Item::Import { file_name, span } => {
+            Item::Uniforms { uniforms, span } => {
+                if context.shader_type == crate::parse::context::ShaderType::Library {
+                    return Err(format!("{}\nUniforms cannot be declared in libraries", input.evaluate_span(*span)));
+                }
+
+                // Each uniform is validated like a tagged `let` in the global scope
+                for uniform in uniforms {
+                    let tag = uniform.tag.clone().unwrap_or(Tag { kind: TagKind::Uniform, arguments: Vec::new() });
+                    if tag.kind != TagKind::Uniform {
+                        return Err(format!("{}\nUniform '{}' cannot be tagged as '{}'", input.evaluate_span(*span), uniform.ident, tag.kind));
+                    }
+
+                    let mut statement = Statement::Let {
+                        tag: Some(tag),
+                        ident: uniform.ident.clone(),
+                        mutable: false,
+                        ty: Some(uniform.ty.clone()),
+                        expression: Some(uniform.default.clone()),
+                        span: uniform.default.span,
+                    };
+                    validate_statement(&mut statement, context, input)?;
+
+                    if let Statement::Let { ty: Some(ty), expression: Some(default), .. } = statement {
+                        uniform.ty = ty;
+                        uniform.default = default;
+                    }
+                }
+            }
+
+            Item::Import { path, alias, span } => {
                 let span = input.evaluate_span(*span);
-                
-                let mut new_path = input.path.clone();
-                new_path.pop();
-                new_path.push(format!("{}.sdf", file_name));
 
-                let new_input = Input::from_path(new_path).map_err(|e| 
-                    format!("{}\n{}", span, e.to_string())
+                let module = import::module_name(path);
+                let new_input = import::load_library(path, &input.path, &context.options).map_err(|e|
+                    format!("{}\n{}", span, e)
                 )?;
+
+                // Diamond imports share a single copy of the library
+                // Modules are identified by their file, whatever path they were imported with
+                let canonical = canonical_path(&new_input.path);
+                if context.module_graph.loaded(&canonical).is_none() {
+                    context.module_graph.push(canonical.clone(), &module).map_err(|e| format!("{}\n{}", span, e))?;
     
-                if new_input.shader_type != crate::parse::context::ShaderType::Library {
-                    return Err(format!("{}\nTo import another '.sdf' file, it must be tagged as '@LIB' to ensure no shader-type-specifics are leaked", span));
+                    if new_input.shader_type != crate::parse::context::ShaderType::Library {
+                        return Err(format!("{}\nTo import another '.sdf' file, it must be tagged as '@LIB' to ensure no shader-type-specifics are leaked", span));
+                    }
+        
+                    let mut new_ast = crate::parse::parse(&new_input)?;
+
+                    // Library items are prefixed with the module name in GLSL
+                    let prefix = context.module_graph.unique_prefix(&import::module_prefix(path));
+                    let previous = context.enter_module(&canonical, &prefix);
+                    validate_ast(&mut new_ast, &new_input, context)?;
+                    context.exit_module(previous);
+
+                    context.module_graph.pop(Some(new_ast));
                 }
-    
-                let mut new_ast = crate::parse::parse(&new_input)?;
-                validate_ast(&mut new_ast, &new_input, context)?;
-                
-                imports.push((index, new_ast));
+
+                let alias = alias.as_ref().unwrap_or(path.last().unwrap());
+                context.import_module(alias, &canonical).map_err(|e| format!("{}\n{}", span, e))?;
+            }
+
+            Item::Use { module, names, span } => {
+                context.use_items(module, names.as_ref().map(|n| n.as_slice())).map_err(|e|
+                    format!("{}\n{}", input.evaluate_span(*span), e)
+                )?;
             }
 
             Item::Constant(constant) => {
+                constant.ident = context.declare_item(&constant.ident, ItemKind::Constant, constant.public).map_err(|e|
+                    format!("{}\n{}", input.evaluate_span(constant.value.span), e)
+                )?;
+
                 validate_const_declaration(constant, context, input)?;
             }
 
-            Item::Struct { name, fields } => {
+            Item::TypeAlias { name, ty, public, span } => {
+                let span = input.evaluate_span(*span);
+
+                // Resolved before declaring, so `type T = T;` is an error
+                *ty = context.resolve_type(ty).map_err(|e| format!("{}\n{}", span, e))?;
+                *name = context.declare_item(name, ItemKind::TypeAlias, *public).map_err(|e| format!("{}\n{}", span, e))?;
+                context.declare_type_alias(name.clone(), ty.clone());
+            }
+
+            Item::Struct { name, fields, public, .. } => {
+                *name = context.declare_item(name, ItemKind::Struct, *public)?;
+
                 for (field, ty, expression) in fields.iter_mut() {
+                    *ty = context.resolve_type(ty)?;
+
                     if let Some(expr) = expression {
                         validate_expression(expr, context, input)?;
                      
-                        let castable = glsl::castable(&context.expression_type(expr)?, &ty.as_string())?;
+                        let castable = glsl::castable(&context.expression_type(expr)?, &context.validate_type(ty)?);
                         if !castable {
                             // TODO: Better error
                             return Err(format!("Incompatible type assigned to field '{}' of '{}'", field, name));
@@ -79,67 +223,52 @@ pub fn validate_ast(ast: &mut AST, input: &Input, context: &mut Context) -> Resu
                 context.declare_struct(name.clone(), &fields)?;
             }
 
-            // TODO: Ensure that return statement has same type as function
-            // TODO: Ensure that typed functions *have* a return statement
-            Item::Function { name, parameters, return_type, statements } => {               
-                context.declare_function(name.clone(), parameters.clone(), return_type.clone())?;
+            Item::Function { name, parameters, return_type, statements, public: _, span } => {
+                let display_name = match context.module_prefix() {
+                    "" => name.clone(),
+                    prefix => name.strip_prefix(&format!("{}__", prefix)).unwrap_or(name).to_owned(),
+                };
 
-                context.scopes.push_scope(ScopeType::Function{ return_type: return_type.clone() });
+                context.scopes.push_scope(ScopeType::Function{ return_type: context.validate_return_type(return_type)? });
 
-                for (_param_qual, param_name, param_type) in parameters {
-                    context.add_var_to_scope(param_name.clone(), param_type.clone(), false)?;
+                for (_param_qual, param_name, param_type) in parameters.iter() {
+                    context.add_var_to_scope(param_name.clone(), context.validate_type(param_type)?, Mutability::Mutable)?;
                 }
 
-                for statement in statements {
+                for statement in statements.iter_mut() {
                     validate_statement(statement, context, input)?;
                 }
 
                 context.scopes.pop_scope();
+
+                flow::check_function(&display_name, &context.validate_return_type(return_type)?, statements, *span, input)?;
+                flow::check_outputs(&display_name, parameters, statements, *span, context, input)?;
             }
 
             Item::Implementation { struct_name, functions  } => {
-                context.validate_type_name(&struct_name)?;
-                context.declare_implementation(struct_name)?;
-
-                if functions.len() == 0 {
-                    return Err(format!("To implement '{}', at least one function is needed", struct_name));
-                }
-
                 for function in functions {
                     match function {
-                        Item::Function { name, parameters, return_type, statements } => {
-                            if parameters.len() > 0 {
-                                let qualifier = if let Some(qual) = &parameters[0].0 {
-                                    qual.clone()
-                                } else {
-                                    FuncParamQualifier::InOut
-                                };
-
-                                if parameters[0].1 != "self" {
-                                    return Err(format!("Implementation function '{}.{}' requires 'self' as first parameter (found '{}')", struct_name, name, parameters[0].1));
-                                }
-
-                                parameters[0] = (Some(qualifier), "self".to_owned(), TypeSpecifier::Identifier(format!("{}", struct_name)));
-                            } else {
-                                return Err(format!("Implementation function '{}.{}' must reference 'self'", struct_name, name));
-                            }
+                        Item::Function { name, parameters, return_type, statements, public: _, span } => {
+                            let method_prefix = format!("__{}__", struct_name);
+                            let display_name = format!("{}.{}", struct_name, name.strip_prefix(&method_prefix).unwrap_or(name));
 
-                            // Memeber functions are represented like so in GLSL
-                            *name = format!("__{}__{}", struct_name, name);
-                            
-                            context.declare_function(name.to_owned(), parameters.clone(), return_type.clone())?;
-                            
-                            context.scopes.push_scope(ScopeType::Function{ return_type: return_type.clone() });
+                            context.scopes.push_scope(ScopeType::Function{ return_type: context.validate_return_type(return_type)? });
 
-                            for (_qual, param_name, param_type) in parameters {
-                                context.add_var_to_scope(param_name.clone(), param_type.clone(), false)?;
+                            // `in self` is a copy, so modifying it would have no effect
+                            for (qual, param_name, param_type) in parameters.iter() {
+                                let read_only = param_name == "self" && *qual == Some(FuncParamQualifier::In);
+                                let mutability = if read_only { Mutability::Constant } else { Mutability::Mutable };
+                                context.add_var_to_scope(param_name.clone(), context.validate_type(param_type)?, mutability)?;
                             }
 
-                            for statement in statements {
+                            for statement in statements.iter_mut() {
                                 validate_statement(statement, context, input)?;
                             }
 
                             context.scopes.pop_scope();
+
+                            flow::check_function(&display_name, &context.validate_return_type(return_type)?, statements, *span, input)?;
+                            flow::check_outputs(&display_name, parameters, statements, *span, context, input)?;
                         }
                         _ => {}
                     }
@@ -149,15 +278,215 @@ pub fn validate_ast(ast: &mut AST, input: &Input, context: &mut Context) -> Resu
             Item::Scene { name, statements } => {
                 // TODO: This
             }
+
+            Item::Cfg { .. } => unreachable!("cfg items are resolved before validation"),
+            Item::Lint { .. } => unreachable!("lint attributes are resolved before validation"),
         }
+
+        let generated = context.take_tuple_structs();
+        if !generated.is_empty() {
+            tuple_structs.push((index, generated));
+        }
+    }
+
+    // Reverse so earlier indices are unaffected by insertions
+    for (index, structs) in tuple_structs.into_iter().rev() {
+        ast.splice(index..index, structs);
     }
 
-    // Merge the ASTs generated by imports into the main AST
-    for (import_index, imported_ast) in imports {
-        // Reverse because items were pushed to a stack (item ordering matters in GLSL)
-        for item in imported_ast.into_iter().rev() {
-            ast.insert(import_index + 1, item);
+    order::sort_functions(ast, context.module_prefix(), input)?;
+
+    lint::check(ast, &attributes, context, input)
+}
+
+/// Declares the signature of a function (or of each function of an implementation), so it can be called
+/// before its body is validated
+fn declare_signature(item: &mut Item, context: &mut Context) -> Result<(), String> {
+    match item {
+        Item::Function { name, parameters, return_type, public, .. } => {
+            resolve_function_types(parameters, return_type, context)?;
+
+            *name = context.declare_item(name, ItemKind::Function, *public)?;
+            context.declare_function(name.clone(), parameters.clone(), return_type)?;
         }
+
+        Item::Implementation { struct_name, functions } => {
+            *struct_name = context.resolve_type_name(struct_name)?;
+
+            let private_methods = functions.iter().filter_map(|function| match function {
+                Item::Function { name, public: false, .. } => Some(name.clone()),
+                _ => None,
+            }).collect();
+            context.declare_implementation(struct_name, private_methods)?;
+
+            if functions.len() == 0 {
+                return Err(format!("To implement '{}', at least one function is needed", struct_name));
+            }
+
+            for function in functions {
+                if let Item::Function { name, parameters, return_type, .. } = function {
+                    if parameters.len() > 0 {
+                        let qualifier = if let Some(qual) = &parameters[0].0 {
+                            qual.clone()
+                        } else {
+                            FuncParamQualifier::InOut
+                        };
+
+                        if parameters[0].1 != "self" {
+                            return Err(format!("Implementation function '{}.{}' requires 'self' as first parameter (found '{}')", struct_name, name, parameters[0].1));
+                        }
+
+                        parameters[0] = (Some(qualifier), "self".to_owned(), TypeSpecifier::Identifier(format!("{}", struct_name)));
+                    } else {
+                        return Err(format!("Implementation function '{}.{}' must reference 'self'", struct_name, name));
+                    }
+
+                    resolve_function_types(&mut parameters[1..], return_type, context)?;
+
+                    // Memeber functions are represented like so in GLSL
+                    *name = format!("__{}__{}", struct_name, name);
+
+                    context.declare_function(name.to_owned(), parameters.clone(), return_type)?;
+                }
+            }
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Allows tuple expressions such as `(1, 2)` where a tuple of castable types, like `(float, int)`, is expected
+fn coerce_tuple(expression: &mut Expression, expected_type: &Type, context: &Context) -> Result<bool, String> {
+    let elements = match (expression, context.tuple_elements(expected_type)) {
+        (Expression::Tuple { expressions, ty }, Some(elements)) if expressions.len() == elements.len() => {
+            for (expr, element) in expressions.iter_mut().zip(elements) {
+                let expr_type = context.expression_type(expr)?;
+
+                if expr_type == *element || coerce_tuple(expr, element, context)? {
+                    continue;
+                }
+
+                // Only primitives have casts
+                if !glsl::castable(&expr_type, element) {
+                    return Ok(false);
+                }
+
+                // GLSL does not implicitly convert struct constructor arguments
+                {
+                    let value = std::mem::replace(expr.as_mut(), Expression::Identifier(String::new()));
+                    **expr = Expression::Binary {
+                        lhs: Box::new(value),
+                        operator: BinaryOperator::Cast,
+                        rhs: Box::new(Expression::Identifier(element.to_string())),
+                        ty: Some(element.clone()),
+                    };
+                }
+            }
+
+            *ty = Some(expected_type.clone());
+            true
+        }
+
+        _ => false,
+    };
+
+    Ok(elements)
+}
+
+/// Declares the variables bound by a pattern, given the type of the value it destructures
+fn bind_pattern(pattern: &mut Pattern, ty: &Type, context: &mut Context) -> Result<(), String> {
+    match pattern {
+        Pattern::Binding { ident, ty: binding_type, mutable } => {
+            if ident.starts_with("gl_") {
+                return Err(format!("The prefix 'gl_' is reserved (used in '{}')", ident));
+            }
+
+            if ident != "_" {
+                context.add_var_to_scope(ident.clone(), ty.clone(), context.pattern_mutability(*mutable))?;
+            }
+
+            *binding_type = Some(ty.specifier());
+        }
+
+        Pattern::Tuple(patterns) if matches!(ty, Type::Vector(..)) => {
+            // `let (x, y, z) = position;` binds each component
+            if Some(patterns.len()) != ty.size() {
+                return Err(format!("Expected a vec with {} components, but found '{}'", patterns.len(), ty));
+            }
+
+            let component_type = context.index_type(ty)?;
+            for pattern in patterns.iter_mut() {
+                bind_pattern(pattern, &component_type, context)?;
+            }
+
+            *pattern = Pattern::Vector(std::mem::take(patterns));
+        }
+
+        Pattern::Tuple(patterns) => {
+            let elements = match context.tuple_elements(ty) {
+                Some(elements) => elements.clone(),
+                None => return Err(format!("Cannot destructure type '{}' with a tuple pattern", ty)),
+            };
+
+            if elements.len() != patterns.len() {
+                return Err(format!("Expected a tuple with {} elements, but found '{}' with {} elements", 
+                    patterns.len(), tuple_string(&elements), elements.len()));
+            }
+
+            for (pattern, element) in patterns.iter_mut().zip(elements.iter()) {
+                bind_pattern(pattern, element, context)?;
+            }
+        }
+
+        Pattern::Struct { name, fields, rest } => {
+            *name = context.resolve_type_name(name)?;
+            if Type::Struct(name.clone()) != *ty {
+                return Err(format!("Cannot destructure type '{}' with a pattern of struct '{}'", ty, name));
+            }
+
+            let mut seen = std::collections::HashSet::new();
+            for (field, pattern) in fields.iter_mut() {
+                if !seen.insert(field.clone()) {
+                    return Err(format!("Field '{}' was bound multiple times", field));
+                }
+
+                let field_type = context.struct_field_type(ty, field)?.clone();
+                bind_pattern(pattern, &field_type, context)?;
+            }
+
+            if !*rest {
+                let missing: Vec<String> = context.struct_field_names(name)
+                    .into_iter()
+                    .filter(|field| !seen.contains(field))
+                    .collect();
+
+                if !missing.is_empty() {
+                    return Err(format!("Pattern does not bind the field(s) {} of '{}'. Use '..' to ignore them", missing.join(", "), name));
+                }
+            }
+        }
+
+        Pattern::Vector(_) => unreachable!("vector patterns are created during validation"),
+    }
+
+    Ok(())
+}
+
+/// Identifies a file regardless of how it was referred to
+fn canonical_path(path: &std::path::Path) -> std::path::PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+/// Replace parameter and return type names with their GLSL names
+fn resolve_function_types(parameters: &mut [(Option<FuncParamQualifier>, String, TypeSpecifier)], return_type: &mut TypeSpecifier, context: &mut Context) -> Result<(), String> {
+    for (_qualifier, _name, ty) in parameters.iter_mut() {
+        *ty = context.resolve_type(ty)?;
+    }
+
+    if return_type.type_name() != "void" {
+        *return_type = context.resolve_type(return_type)?;
     }
 
     Ok(())
@@ -176,14 +505,26 @@ fn validate_const_declaration(constant: &mut ConstDeclaration, context: &mut Con
         return Err(format!("{}\nThe prefix 'gl_' is reserved (used in '{}')", span, constant.ident));
     }
 
-    context.validate_type(&constant.ty).map_err(|e|
+    constant.ty = context.resolve_type(&constant.ty).map_err(|e|
         format!("{}\n{}", span, e)
     )?;
+    let constant_type = context.validate_type(&constant.ty).map_err(|e|
+        format!("{}\n{}", span, e)
+    )?;
+
+    // GLSL constant expressions may only call builtins (and global constants are validated before any function is declared)
+    let calls = reach::expression_references(&constant.value.expression).calls;
+    let user_call = calls.iter()
+        .filter(|call| !glsl::functions::is_builtin(call) && !glsl::vec::is_vec_constructor_or_type(call) && !glsl::mat::is_mat_constructor_or_type(call))
+        .min();
+    if let Some(call) = user_call {
+        return Err(format!("{}\nConstant '{}' cannot call '{}' (constants may only call builtin functions and constructors)", span, constant.ident, call));
+    }
 
     validate_expression(&mut constant.value.expression, context, input)?;
 
     let mut castable = false;
-    if let TypeSpecifier::Array { ty: _, size } = &constant.ty {
+    if let Type::Array(element, size) = &constant_type {
         if let Expression::ArrayConstructor { ty: _, expressions } = &constant.value.expression {        
             if *size as usize != expressions.len() {
                 return Err(format!("{}\n Array '{}' was declared as length '{}', but assigned to array of length '{}'", span, constant.ident, size, expressions.len()));
@@ -191,11 +532,9 @@ fn validate_const_declaration(constant: &mut ConstDeclaration, context: &mut Con
 
             for (i, expr) in expressions.iter().enumerate() {
                 let expr_type = context.expression_type(&expr)?;
-                castable = glsl::castable(&expr_type, &constant.ty.as_string().split("[").next().unwrap()).map_err(|e|
-                    format!("{}\n{}", span, e)
-                )?;
+                castable = glsl::castable(&expr_type, element);
                 if !castable {
-                    return Err(format!("{}\nCould not create array of type '{}' from array initializer (argument {} is incompatible of type '{}')", span, constant.ty, i, expr_type));
+                    return Err(format!("{}\nCould not create array of type '{}' from array initializer (argument {} is incompatible of type '{}')", span, constant_type, i, expr_type));
                 }
             }
         }
@@ -205,14 +544,13 @@ fn validate_const_declaration(constant: &mut ConstDeclaration, context: &mut Con
         format!("{}\n{}", span, e)
     )?;
     if !castable {
-        castable = glsl::castable(rhs_type, &constant.ty.as_string()).map_err(|e|
-            format!("{}\n{}", span, e)
-        )?;
+        castable = glsl::castable(rhs_type, &constant_type);
     }
 
     if castable {
         // This will be pushed to the global scope by default (no need to push/pop scope)
-        context.add_var_to_scope(constant.ident.clone(), constant.ty.clone(), true)?;
+        context.add_var_to_scope(constant.ident.clone(), constant_type, Mutability::Constant)?;
+        context.set_constant_value(&constant.ident, constant.value.expression.clone());
     } else {
         return Err(format!("{}\nCannot assign the constant '{}' of type '{}' to incompatible type '{}'", span, constant.ident, constant.ty, rhs_type));
     }
@@ -233,93 +571,132 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
             validate_const_declaration(constant, context, input)?;
         }
 
-        Statement::Let { ident, tag, ty, expression } => {                       
+        Statement::Let { ident, mutable, tag, ty, expression, span } => {
             if ident.starts_with("gl_") {
                 return Err(format!("The prefix 'gl_' is reserved (used in '{}')", ident));
             }
-            
+
             if let Some(assignment) = expression {
                 validate_expression(&mut assignment.expression, context, input).map_err(|e|
                     format!("{}\n{}", input.evaluate_span(assignment.span), e)
                 )?;
+            }
 
-                // Special cases
+            let specified_type = match ty {
+                Some(specified_type) => {
+                    *specified_type = context.resolve_type(specified_type).map_err(|e| format!("{}\n{}", input.evaluate_span(*span), e))?;
+                    Some(context.validate_type(specified_type)?)
+                }
+                None => None,
+            };
+
+            if let Some(assignment) = expression {
+                // Special case: `let x: uint = 1;` emits `1u` rather than an implicit conversion
                 if let Expression::Literal(lit) = &mut assignment.expression {
                     if let Literal::Int(i) = lit {
-                        if let Some(t) = ty {
-                            if t.as_string() == "uint" {
-                                *lit = Literal::UInt(*i as u32);
-                            }
-                        }
-                    }
-                } else if context.expression_type(&mut assignment.expression)? == "uint" {
-                    if let Some(t) = ty {
-                        if t.as_string() == "int" {
-                            return Err(format!("{}\nCannot assign 'int' to 'uint' expression", input.evaluate_span(assignment.span)));
+                        if specified_type == Some(Type::UINT) {
+                            *lit = Literal::UInt(*i as u32);
                         }
                     }
                 }
             }
             
-            // Tagged variables must have specified type and initial value
+            // Tagged variables must have a specified type
             if let Some(t) = tag {
-                if expression.is_none() {
-                    return Err(format!("Variable '{}' was tagged as '{:?}', but not initialized", ident, t));
-                }
+                let (specifier, specified_type) = match (ty.as_ref(), &specified_type) {
+                    (Some(specifier), Some(specified_type)) => (specifier, specified_type),
+                    _ => return Err(format!("Variable '{}' was tagged as '{}', but its type was not specified", ident, t.kind)),
+                };
+
+                // Tag errors point to the declaration
+                let at = |e| format!("{}\n{}", input.evaluate_span(*span), e);
 
-                if let Some(specified_type) = ty {   
-                    match t {
-                        Tag::Uniform => {
-                            context.declare_uniform(ident.clone(), specified_type.clone())?
+                match t.kind {
+                    TagKind::Uniform => {
+                        check_tag_arguments(t, &["location", "min", "max", "step", "label", "widget", "semantic"]).map_err(at)?;
+
+                        if specified_type.is_sampler() {
+                            return Err(format!("Variable '{}' is a sampler, so it must be tagged as '@texture' rather than '@uniform'", ident));
                         }
 
-                        _ => {
-                            unimplemented!();
+                        let assignment = match expression {
+                            Some(assignment) => assignment,
+                            None => return Err(format!("Variable '{}' was tagged as '{}', but not initialized", ident, t.kind)),
+                        };
+
+                        let default = inline_constants(&assignment.expression, context);
+                        check_uniform_default(&default).map_err(|e|
+                            format!("{}\n{}", input.evaluate_span(assignment.span), e)
+                        )?;
+
+                        let location = tag_index(t, "location", ident).map_err(at)?;
+                        let meta = uniform_meta(t, specified_type, ident).map_err(at)?;
+
+                        // The runtime would clamp the default on the first frame
+                        check_default_range(&default, &meta, ident).map_err(|e|
+                            format!("{}\n{}", input.evaluate_span(assignment.span), e)
+                        )?;
+
+                        context.declare_uniform(ident.clone(), specifier.clone(), location, meta).map_err(at)?
+                    }
+
+                    // Textures are bound by the host, so they cannot be initialized
+                    TagKind::Texture => {
+                        check_tag_arguments(t, &["binding"]).map_err(at)?;
+
+                        if let Some(assignment) = expression {
+                            return Err(format!("{}\nTexture '{}' cannot be initialized (it is bound by the host)", input.evaluate_span(assignment.span), ident));
                         }
+
+                        let binding = tag_index(t, "binding", ident).map_err(at)?;
+                        context.declare_texture(ident.clone(), specifier.clone(), binding).map_err(at)?;
                     }
-                } else {
-                    return Err(format!("Variable '{}' was tagged as '{:?}', but its type was not specified", ident, t));
+
+                    // Outputs are assigned in place (if initialized), but declared globally
+                    TagKind::Out => {
+                        check_tag_arguments(t, &["location"]).map_err(at)?;
+
+                        let location = tag_index(t, "location", ident).map_err(at)?;
+                        context.declare_out(ident.clone(), specifier.clone(), location).map_err(at)?;
+                    }
+                }
+            } else if let Some(specified_type) = &specified_type {
+                if specified_type.is_sampler() {
+                    return Err(format!("Variable '{}' is a sampler, so it must be declared with '@texture'", ident));
                 }
             }
 
-            let mut checked_type = if let Some(specified_type) = ty {
-                context.validate_type(specified_type)?;
+            let checked_type = if let Some(specified_type) = specified_type {
                 // Check whether type assigned is compatible with user-specified
                 if let Some(assignment) = expression { 
                     let span = input.evaluate_span(assignment.span);     
 
-                    let assigned_type = context.expression_type(&mut assignment.expression).map_err(|e| 
+                    let assigned_type = context.expression_type(&assignment.expression).map_err(|e| 
                         format!("{}\n{}", span, e)
                     )?;
 
                     // If specified type is an array, check if assigned array type is of compatible type
-                    
-                    // FIXME: This first branch is very hacky
-                    let castable = if let TypeSpecifier::Array { ty, size } = specified_type {
+                    let castable = if let Type::Array(element, size) = &specified_type {
                         // If RHS is an anonymous array, check compatibility, and fix its type
                         if let Expression::ArrayConstructor { expressions, ty: array_t } = &mut assignment.expression {
                             if expressions.len() != *size as usize {
                                 return Err(format!("{}\nVariable '{}' was assigned to a differently sized array (expected size {}, got {})", span, ident, size, expressions.len()));
                             }
                             
-                            let c = glsl::castable(&assigned_type.split("[").next().unwrap(), &array_t).map_err(|e| 
-                                format!("{}\n{}", span, e)
-                            )?;
+                            let c = glsl::castable(&context.index_type(&assigned_type)?, element);
 
                             if c {
-                                *array_t = ty.clone();
+                                *array_t = Some(element.as_ref().clone());
                             }
 
                             c
                         } else {
-                            glsl::castable(&assigned_type, &specified_type.as_string()).map_err(|e| 
-                                format!("{}\n{}", span, e)
-                            )?
+                            glsl::castable(&assigned_type, &specified_type)
                         }
+                    } else if coerce_tuple(&mut assignment.expression, &specified_type, context)? {
+                        true
                     } else {   
-                        glsl::castable(&assigned_type, &specified_type.as_string()).map_err(|e| 
-                            format!("{}\n{}", span, e)
-                        )?
+                        glsl::castable(&assigned_type, &specified_type)
                     };
 
                     if !castable {
@@ -327,45 +704,84 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
                                                 span, &ident, specified_type, &assigned_type));
                     }
                 }
-                Some(specified_type.clone())
+                specified_type
             } else {
                 // Make sure inferred type is valid (not void like a void function call)
-                if let Some(assignment) = &expression {
-                    let expr_type = context.expression_type(&assignment.expression).map_err(|e| 
-                        format!("{}\n{}", input.evaluate_span(assignment.span), e)
-                    )?;
-                    if expr_type != "void" {
-                        Some(TypeSpecifier::Identifier(expr_type))
-                    } else {
-                        return Err(format!("{}\nVariable '{}' was assigned type 'void'.", input.evaluate_span(assignment.span), &ident));
+                match &expression {
+                    Some(assignment) => {
+                        let expr_type = context.expression_type(&assignment.expression).map_err(|e| 
+                            format!("{}\n{}", input.evaluate_span(assignment.span), e)
+                        )?;
+                        if expr_type == Type::Void {
+                            return Err(format!("{}\nVariable '{}' was assigned type 'void'.", input.evaluate_span(assignment.span), &ident));
+                        }
+
+                        // The inferred type is needed to declare the variable in GLSL
+                        *ty = Some(expr_type.specifier());
+                        expr_type
                     }
-                } else {
-                    None
+                    None => return Err(format!("Variable '{}' needs either a type or an initial value", ident)),
                 }
             };
 
-            // FIXME: Hack until context::expression_type returns TypeSpecifier
-            if let Some(t) = &mut checked_type {
-                let ty = t.as_string();
-                if ty.contains("[") {
-                    let mut sp = ty.split("[");
-                    let ty = sp.next().unwrap().to_owned();
-                    let mut size = sp.next().unwrap().to_owned();
-                    size.pop();
-                    *t = TypeSpecifier::Array {
-                        ty,
-                        size: size.parse::<u32>().unwrap(),
-                    };
-                }
+            // Outputs are written by the shader, while uniforms and textures are only set by the CPU
+            let mutability = match tag {
+                Some(Tag { kind: TagKind::Out, .. }) => Mutability::Mutable,
+                Some(_) => Mutability::Constant,
+                None => context.let_mutability(*mutable),
+            };
+            if expression.is_none() && mutability == Mutability::Immutable {
+                return Err(format!("{}\nVariable '{}' has no initial value, so it must be declared with 'let mut' to be assigned later", input.evaluate_span(*span), ident));
+            }
+
+            // The (now type-checked) initializer of a uniform is its default value
+            if let (Some(Tag { kind: TagKind::Uniform, .. }), Some(assignment)) = (tag, expression) {
+                let default = inline_constants(&assignment.expression, context);
+                context.set_uniform_default(ident, default);
             }
 
-            *ty = checked_type.clone();
+            context.add_var_to_scope(ident.clone(), checked_type, mutability)?;
+        }
+
+        Statement::LetPattern { pattern, ty, expression, temp } => {
+            let span = input.evaluate_span(expression.span);
+
+            validate_expression(&mut expression.expression, context, input).map_err(|e|
+                format!("{}\n{}", span, e)
+            )?;
+
+            let assigned_type = context.expression_type(&expression.expression).map_err(|e|
+                format!("{}\n{}", span, e)
+            )?;
+
+            let value_type = match ty {
+                Some(specified_type) => {
+                    *specified_type = context.resolve_type(specified_type).map_err(|e| format!("{}\n{}", span, e))?;
+                    let value_type = context.validate_type(specified_type).map_err(|e| format!("{}\n{}", span, e))?;
 
-            context.add_var_to_scope(ident.clone(), checked_type.unwrap(), false)?;
+                    if !coerce_tuple(&mut expression.expression, &value_type, context)?
+                        && !glsl::castable(&assigned_type, &value_type)
+                    {
+                        return Err(format!("{}\nPattern was declared as type '{}', but assigned to an incompatible type: '{}'", 
+                                                span, value_type, assigned_type));
+                    }
+
+                    value_type
+                }
+
+                None => assigned_type,
+            };
+
+            *temp = context.temporary("pattern");
+            bind_pattern(pattern, &value_type, context).map_err(|e| format!("{}\n{}", span, e))?;
+            *ty = Some(value_type.specifier());
         }
 
-        Statement::LetConstructor { ident, constructor } => {
-            context.add_var_to_scope(ident.clone(), TypeSpecifier::Identifier(constructor.ty.clone()), false)?;
+        Statement::LetConstructor { ident, mutable, constructor, span } => {
+            let span = input.evaluate_span(*span);
+
+            constructor.ty = context.resolve_type_name(&constructor.ty).map_err(|e| format!("{}\n{}", span, e))?;
+            context.add_var_to_scope(ident.clone(), Type::Struct(constructor.ty.clone()), context.let_mutability(*mutable))?;
             
             for (_ident, field) in &mut constructor.fields {
                 validate_expression(&mut field.expression, context, input)?;
@@ -373,12 +789,7 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
             
             // Order the fields and fill in defaults
             constructor.fields = context.generate_constructor(&constructor.ty, constructor.fields.clone()).map_err(|e|
-                // FIXME: Temporary hack to get a span for the LetConstructor
-                if let Some(a) = constructor.fields.get(0) {
-                    format!("{}\n{}", input.evaluate_span(a.1.span), e)
-                } else {
-                    e
-                }
+                format!("{}\n{}", span, e)
             )?;
         }
 
@@ -386,67 +797,36 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
         Statement::Assignment { lhs, op, expression } => {
             let span = input.evaluate_span(expression.span);
             
-            let mut lhs_type = "__temp__".to_owned();
-
             // Only affects array index unary expression
-            validate_expression(&mut lhs.expression, context, input)?;
+            validate_expression(&mut lhs.expression, context, input).map_err(|e|
+                format!("{}\n{}", span, e)
+            )?;
             
-            // Determine lhs type
-            match &mut lhs.expression {
-                Expression::Unary { operator: UnaryOperator::Index(index_expr), expr: _, ty } => {
-                    validate_expression(index_expr.as_mut(), context, input)?;
-                    
-                    let index_type = context.expression_type(index_expr.as_ref())?;
-                    if !glsl::castable(&index_type, "int")? {
-                        return Err(format!("{}\nCannot index using type '{}', must be castable to 'int'", span, index_type));
-                    }
-
-                    lhs_type = ty.split("[").next().unwrap().to_owned();
-                }
+            // Includes fields and elements (`gl_FragCoord.x = ...`)
+            if let Some(variable) = assigned_variable(&lhs.expression) {
+                check_mutable(variable, context).map_err(|e| format!("{}\n{}", span, e))?;
+            }
 
-                Expression::Identifier(ident) => {
-                    let is_constant = context.scopes.is_var_constant(ident).map_err(|e|
-                        format!("{}\n{}", input.evaluate_span(expression.span), e)
+            // Determine lhs type
+            let lhs_type = match &mut lhs.expression {
+                Expression::Unary { operator: UnaryOperator::Index(_), .. } => {
+                    validate_expression(&mut lhs.expression, context, input).map_err(|e|
+                        format!("{}\n{}", span, e)
                     )?;
-                    if is_constant {
-                        return Err(format!("{}\nCannot assign to an identifier declared as constant", input.evaluate_span(expression.span)));
-                    }
 
-                    lhs_type = context.scopes.var_type(ident)?.as_string();
+                    context.expression_type(&lhs.expression)?
                 }
 
+                Expression::Identifier(ident) => context.scopes.var_type(ident).map_err(|e| format!("{}\n{}", span, e))?.clone(),
+
                 
                 // lhs must be a series of identifiers and fields. No functions.
-                Expression::Member(member) => {
-                    for item in &member.path {
-                        match item {
-                            IdentOrFunction::Ident(ident) => {
-                                // First item is a variable. The rest are fields.
-                                if lhs_type == "__temp__" {
-                                    lhs_type = context.scopes.var_type(ident)?.as_string();
-                                } else {
-                                    // Check if lhs is the field of a vec
-                                    if glsl::vec::is_vec_constructor_or_type(&lhs_type) {
-                                        // Ensure that swizzle is op-assignment valid (can be more than length 1)
-                                        lhs_type = glsl::vec::validate_swizzle_for_assignment(&lhs_type, ident)?;
-                                    } else {   
-                                        lhs_type = context.struct_field_type(&lhs_type, ident)?.as_string();
-                                    }
-                                }
-                            }
-
-                            // TODO: Is this always true? Or are there cases where this would be valid?
-                            IdentOrFunction::Function(func) => {
-                                return Err(format!("{}\nCannot assign to '.' operator with function call '{}'", span, func.name));
-                            }
-                        }
-                    }
-                }
+                Expression::Member(member) => member_place_type(member, context).map_err(|e| format!("{}\n{}", span, e))?,
 
                 _ => {
                     return Err(format!("{}\nAssignment only works for identifiers, struct fields, and array indexes (tried assigning to '{:?}')", input.evaluate_span(lhs.span), lhs.expression));
                 }
-            }
+            };
 
             // rhs
             validate_expression(&mut expression.expression, context, input).map_err(|e| 
@@ -461,27 +841,28 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
                     expr_type
                 }
 
-                _ => {                    
+                _ => {
+                    let operator = match op {
+                        AssignmentOperator::AddAssign => BinaryOperator::Plus,
+                        AssignmentOperator::SubtractAssign => BinaryOperator::Minus,
+                        AssignmentOperator::MultiplyAssign => BinaryOperator::Multiply,
+                        _ => BinaryOperator::Divide,
+                    };
+
                     // The result of (lhs op rhs) should be castable to the type of (lhs)
                     // This is useful for types like 'vec' where (lhs op rhs) is not always obvious
-                    context.add_type(&lhs_type, &expr_type).map_err(|e|
+                    glsl::operators::binary_type(&operator, &lhs_type, &expr_type).map_err(|e|
                         format!("{}\n{}", span, e)
                     )?
                 }
             };
 
-            let castable = glsl::castable(&result_type, &lhs_type).map_err(|e|
-                format!("{}\n{}", span, e)
-            )?;
-
-            if !castable {
+            if !glsl::castable(&result_type, &lhs_type) {
                 return Err(format!("{}\nInvalid assignment statement. Cannot assign type '{}' to incompatible type '{}'", span, &lhs_type, &result_type));
             }
         }
 
-        // TODO: Ensure non-void function always return
-        // TODO: Ensure if statements always lead to eventual returns
-        Statement::Return { expression } => {
+        Statement::Return { expression, span: _ } => {
             let expected_type = context.scopes.expected_return_type()?;            
 
             if let Some(expr) = expression {
@@ -496,7 +877,7 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
                 )?;
 
                 let mut castable = false;
-                if let TypeSpecifier::Array { ty, size } = &expected_type {
+                if let Type::Array(element, size) = &expected_type {
                     if let Expression::ArrayConstructor { expressions, ty } = &mut expr.expression {
                         if expressions.len() != *size as usize {
                             return Err(format!("{}\nExpected array of length {}, but got one of length {}", span, size, expressions.len()));
@@ -504,26 +885,28 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
                         
                         for (i, e) in expressions.iter().enumerate() {
                             let expr_type = context.expression_type(e)?;
-                            if !glsl::castable(&expr_type, expected_type.type_name())? {
+                            if !glsl::castable(&expr_type, element) {
                                 return Err(format!("{}\nReturned array value number {} is of type '{}' which is incompatible with array type '{}'", span, i+1, expr_type, expected_type));
                             }
                         }
-                        *ty = expected_type.type_name().to_owned();
+                        *ty = Some(element.as_ref().clone());
                         castable = true;
                     } 
                 }
 
                 if !castable {
-                    castable = glsl::castable(&ty, &expected_type.as_string()).map_err(|e| 
-                        format!("{}\n{}", span, e)
-                    )?;
+                    castable = coerce_tuple(&mut expr.expression, &expected_type, context)?;
+                }
+
+                if !castable {
+                    castable = glsl::castable(&ty, &expected_type);
                 }
 
                 if !castable {
                     return Err(format!("{}\nExpected return type of '{}', but got incompatible type '{}'", span, expected_type, ty));
                 }
             } else {
-                if expected_type.as_string() != "void" {
+                if expected_type != Type::Void {
                     return Err(format!("Expected a '{}' return type, but found none", expected_type));
                 }
             }
@@ -534,6 +917,12 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
             
             context.scopes.push_scope(ScopeType::Loop);
             
+            for bound in [&mut *from, &mut *to] {
+                validate_expression(&mut bound.expression, context, input).map_err(|e|
+                    format!("{}\n{}", input.evaluate_span(bound.span), e)
+                )?;
+            }
+
             let from_type = context.expression_type(&from.expression).map_err(|e|
                 format!("{}\n{}", input.evaluate_span(from.span), e)
             )?;
@@ -541,8 +930,9 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
                 format!("{}\n{}", input.evaluate_span(to.span), e)
             )?;
 
-            if (from_type == "int" || from_type == "uint") && (to_type == "int" || to_type == "uint") {
-                context.add_var_to_scope(loop_var.clone(), TypeSpecifier::from_ident("int"), false).map_err(|e| 
+            let integer = |ty: &Type| *ty == Type::INT || *ty == Type::UINT;
+            if integer(&from_type) && integer(&to_type) {
+                context.add_var_to_scope(loop_var.clone(), Type::INT, Mutability::Mutable).map_err(|e| 
                     // FIXME: Using "from.span" is a (viable) hack. Should use the for's span when implemented
                     format!("{}\n{}", input.evaluate_span(from.span), e)
                 )?;
@@ -560,11 +950,15 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
         Statement::While { condition, block, do_while: _ } => {
             let span = input.evaluate_span(condition.span);
             
+            validate_expression(&mut condition.expression, context, input).map_err(|e|
+                format!("{}\n{}", span, e)
+            )?;
+
             let expr_type = context.expression_type(&condition.expression).map_err(|e|
                 format!("{}\n{}", span, e)
             )?;
 
-            if expr_type != "bool" {
+            if expr_type != Type::BOOL {
                 return Err(format!("{}\nWhile loop condition must be boolean", span));
             }
 
@@ -582,6 +976,18 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
                 format!("{}\n{}", input.evaluate_span(*span), e)
             )?;
         }
+
+        Statement::Block { statements, span: _ } => {
+            context.scopes.push_scope(ScopeType::Block);
+
+            for statement in statements {
+                validate_statement(statement, context, input)?;
+            }
+
+            context.scopes.pop_scope();
+        }
+
+        Statement::Cfg { .. } | Statement::IfConst { .. } => unreachable!("cfg statements are resolved before validation"),
     }
 
     Ok(())
@@ -589,16 +995,35 @@ fn validate_statement(statement: &mut Statement, context: &mut Context, input: &
 
 fn validate_expression(expression: &mut Expression, context: &mut Context, input: &Input) -> Result<(), String> {
     match expression {
+        Expression::Tuple { expressions, ty } => {
+            let mut elements = Vec::new();
+
+            for expr in expressions.iter_mut() {
+                validate_expression(expr, context, input)?;
+
+                let element = context.expression_type(expr)?;
+                if element == Type::Void {
+                    return Err("Tuples cannot contain 'void' values".to_owned());
+                }
+
+                elements.push(element);
+            }
+
+            *ty = Some(context.tuple_type(elements)?);
+        }
+
         Expression::ArrayConstructor { expressions, ty } => {
             // TODO: All types need to be checked as compatible (and allow for casting)
             // TODO: Infer type based on *least* compatible type (upcast everything, and that is expected type)
 
             // Casting hierarchy
-            let mut values = std::collections::HashMap::new();
-            values.insert("uint", 0u8);
-            values.insert("int", 1);
-            values.insert("float", 2);
-            values.insert("double", 3);
+            let rank = |ty: &Type| match ty {
+                Type::Scalar(Scalar::UInt) => Some(0),
+                Type::Scalar(Scalar::Int) => Some(1),
+                Type::Scalar(Scalar::Float) => Some(2),
+                Type::Scalar(Scalar::Double) => Some(3),
+                _ => None,
+            };
 
             for expr in expressions.iter_mut() {
                 validate_expression(expr, context, input)?;
@@ -607,22 +1032,18 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
             let mut expected_type = context.expression_type(&expressions[0])?;
             for expr in expressions.iter().skip(1) {
                 let this_type = context.expression_type(expr)?;
-                if !glsl::castable(&this_type, &expected_type)? {
-                    if values.contains_key(this_type.as_str()) && values.contains_key(expected_type.as_str()) {
-                        if values.get(this_type.as_str()).unwrap() > values.get(expected_type.as_str()).unwrap() {
-                            expected_type = this_type;
-                        } else {
-                            // FIXME: Better error
-                            return Err(format!("Incompatible types"))
-                        }
-                    } else {
+                if !glsl::castable(&this_type, &expected_type) {
+                    match (rank(&this_type), rank(&expected_type)) {
+                        (Some(this), Some(expected)) if this > expected => expected_type = this_type,
                         // FIXME: Better error
-                        return Err(format!("Incompatible array types"));
+                        (Some(_), Some(_)) => return Err(format!("Incompatible types")),
+                        // FIXME: Better error
+                        _ => return Err(format!("Incompatible array types")),
                     }
                 }
             }
 
-            *ty = expected_type;
+            *ty = Some(expected_type);
             // *ty = context.expression_type(expressions[0].as_ref())?;
         }
 
@@ -632,28 +1053,39 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
 
         // TODO: This only seems plausable if the function accepts exactly 2 parameters
         Expression::FunctionApply(apply) => {
+            if !glsl::functions::is_builtin(&apply.name) {
+                apply.name = context.resolve_function_name(&apply.name)?;
+            }
+
             let mut param_types = Vec::new();
             for expr in apply.parameters.iter_mut() {
                 validate_expression(expr, context, input)?;
-                param_types.push(TypeSpecifier::Identifier(context.expression_type(expr)?));
+                param_types.push(context.expression_type(expr)?);
             }
             
             let (num_params, return_type) = context.check_function_apply(&apply.name, param_types)?;
 
             apply.func_parameters = num_params;
-            apply.ty = return_type.as_string();
+            apply.ty = Some(return_type);
         }
 
         Expression::FunctionCall(call) => {
+            if !glsl::vec::is_vec_constructor_or_type(&call.name) && !glsl::mat::is_mat_constructor_or_type(&call.name)
+                && !glsl::functions::is_builtin(&call.name) 
+            {
+                call.name = context.resolve_function_name(&call.name)?;
+            }
+
             let mut param_types = Vec::new();
             for expr in call.parameters.iter_mut() {
                 validate_expression(expr, context, input)?;
-                param_types.push(TypeSpecifier::Identifier(context.expression_type(expr)?));
+                param_types.push(context.expression_type(expr)?);
             }
             
             let return_type = context.check_function_call(&call.name, param_types)?;
+            check_written_arguments(&call.name, &context.source_name(&call.name), &call.parameters, false, context)?;
 
-            call.ty = return_type.as_string();
+            call.ty = Some(return_type);
         }
 
         Expression::Unary { operator, expr, ty } => {
@@ -665,10 +1097,12 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
                     let index_expr_type = context.expression_type(index_expr)?;
                     
                     // TODO: Need to ensure the int is positive
-                    if !glsl::castable(&index_expr_type, "int")? {
+                    if !glsl::castable(&index_expr_type, &Type::INT) {
                         return Err(format!("Arrays can only be indexed by positive integers (tried indexing with type '{}')", index_expr_type));
                     }
 
+                    *ty = Some(context.index_type(&context.expression_type(&expr)?)?);
+
                     // TODO: Bounds check the array access once TypeSpecifier is fully implemented
                     // if let Expression::Literal(literal) = &**index_expr {
                     //     if let Literal::Int(i) = literal {
@@ -677,26 +1111,25 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
 
                     //     }
                     // }
-
-                    *ty = context.expression_type(&expr)?;
                 }
 
                 UnaryOperator::Negate => {
-                    *ty = context.negate_type(&context.expression_type(expr)?)?;
+                    *ty = Some(context.negate_type(&context.expression_type(expr)?)?);
                 }
 
                 UnaryOperator::Not => {
-                    if context.expression_type(expr)? != "bool" {
-                        return Err(format!("The binary not cannot be used on type '{}'", &ty));
+                    let expr_type = context.expression_type(expr)?;
+                    if expr_type != Type::BOOL {
+                        return Err(format!("The binary not cannot be used on type '{}'", expr_type));
                     }
-                    *ty = "bool".to_owned();
+                    *ty = Some(Type::BOOL);
                 }
             }
         }
 
         Expression::Member(member) => {
-            // First item
-            let mut current_type = String::from("__temp__");
+            // Type of the path so far (`None` before the first item)
+            let mut current_type: Option<Type> = None;
             let mut last_ident = String::new();
 
             let mut to_remove = Vec::new();
@@ -707,29 +1140,48 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
             for (index, item) in member.path.iter_mut().enumerate() {
                 match item {
                     IdentOrFunction::Ident(ident) => {
-                        if current_type == "__temp__" {
+                        current_type = Some(match current_type {
                             // First item must be a variable. Following would be fields.
-                            current_type = context.scopes.var_type(ident)?.as_string();
-                        } else {
+                            None => {
+                                *ident = context.resolve_identifier(ident)?;
+                                context.scopes.var_type(ident)?.clone()
+                            }
                             // If vec type, follow swizzle rules
-                            if glsl::vec::is_vec_constructor_or_type(&current_type) {
-                                // Get the type of the swizzle
-                                current_type = glsl::vec::validate_swizzle(&current_type, &ident)?;
-                            } 
+                            Some(ty @ Type::Vector(..)) => glsl::vec::validate_swizzle(&ty, ident)?,
                             // Otherwise, it is just a normal field
-                            else {
-                                current_type = context.struct_field_type(&current_type, ident)?.as_string();
-                            }
-                        }
+                            Some(ty) => context.struct_field_type(&ty, ident)?.clone(),
+                        });
                         last_ident = ident.clone();
                     }
 
-                    // Note that function calls cannot happen before identifiers
                     IdentOrFunction::Function(func) => {
-                        if current_type == "__temp__" {
-                            return Err(format!("Member methods must be accessed via the '.' operator: '{}'", func.name));
+                        // A free function call like `texture(t, uv).xyz` is validated as usual
+                        let receiver = match &current_type {
+                            Some(receiver) => receiver.clone(),
+                            None => {
+                                let mut call = Expression::FunctionCall(func.clone());
+                                validate_expression(&mut call, context, input)?;
+                                current_type = Some(context.expression_type(&call)?);
+
+                                if let Expression::FunctionCall(validated) = call {
+                                    *func = validated;
+                                }
+
+                                last_ident.clear();
+                                continue;
+                            }
+                        };
+
+                        if last_ident.is_empty() {
+                            return Err(format!("Member methods can only be called on variables: '{}'", func.name));
+                        }
+
+                        if let Type::Struct(struct_name) = &receiver {
+                            context.check_method_access(struct_name, &func.name)?;
                         }
-                        func.name = format!("__{}__{}", current_type, func.name);
+
+                        func.name = format!("__{}__{}", receiver, func.name);
+                        let display_name = context.source_name(&func.name);
 
                         // TODO: Also need to allow fields (not just single ident)
                         func.parameters.insert(0, Expression::Identifier(last_ident.clone()));
@@ -739,11 +1191,12 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
                         let mut param_types = Vec::new();
                         for expr in func.parameters.iter_mut() {
                             validate_expression(expr, context, input)?;
-                            param_types.push(TypeSpecifier::Identifier(context.expression_type(expr)?));
+                            param_types.push(context.expression_type(expr)?);
                         }
 
-                        current_type = context.check_function_call(&func.name, param_types)?.as_string();
-                        func.ty = current_type.clone();
+                        func.ty = Some(context.check_function_call(&func.name, param_types)?);
+                        check_written_arguments(&func.name, &display_name, &func.parameters, true, context)?;
+                        current_type = func.ty.clone();
                     }
                 }
             }
@@ -758,50 +1211,37 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
             validate_expression(rhs, context, input)?;
 
             match operator {
-                // TODO: Some operators like "&&" require lhs and rhs to both be boolean
-                BinaryOperator::EqualTo | BinaryOperator::NotEqualTo | BinaryOperator::GreaterThanOrEqualTo 
-                | BinaryOperator::LessThanOrEqualTo | BinaryOperator::GreaterThan | BinaryOperator::LessThan 
-                | BinaryOperator::And | BinaryOperator::Or  => {}
-
-                BinaryOperator::Multiply | BinaryOperator::Divide => {
-                    let actual_type = context.multiply_type(
-                        &context.expression_type(lhs)?,
-                        &context.expression_type(rhs)?
-                    )?;
-
-                    *ty = actual_type;
-                }
-
-                BinaryOperator::Plus | BinaryOperator::Minus => {
-                    let actual_type = context.add_type(
-                        &context.expression_type(lhs)?,
-                        &context.expression_type(rhs)?
-                    )?;
-
-                    *ty = actual_type;
-                }
-
                 BinaryOperator::Cast => {
                     let lhs_type = context.expression_type(&lhs)?;
                     // let rhs_type = context.expression_type(&rhs);
 
                     match rhs.as_ref() {
                         Expression::Identifier(type_name) => {
-                            if context.is_primitive(&type_name) {
-                                // TODO: Is this correct? Always required for narrowing conversions anyway
-                                if glsl::narrow_castable(&lhs_type, &type_name)? {
-                                // if true {
-                                    *ty = type_name.to_owned();
-                                } else {
-                                    return Err(format!("Cannot cast from type '{}' to '{}'", &lhs_type, &type_name));
-                                }
+                            let target = match Type::from_name(type_name) {
+                                Some(target) if context.is_primitive(type_name) => target,
+                                _ => return Err(format!("Cannot cast to non-primitive type, '{}'", &type_name)),
+                            };
+
+                            // TODO: Is this correct? Always required for narrowing conversions anyway
+                            if glsl::narrow_castable(&lhs_type, &target) {
+                                *ty = Some(target);
                             } else {
-                                return Err(format!("Cannot cast to non-primitive type, '{}'", &type_name));
+                                return Err(format!("Cannot cast from type '{}' to '{}'", &lhs_type, &target));
                             }
                         }
 
                         _ => return Err("Can only cast to type name, not an expression".to_owned()),                    }
                 }
+
+                _ => {
+                    let actual_type = glsl::operators::binary_type(
+                        operator,
+                        &context.expression_type(lhs)?,
+                        &context.expression_type(rhs)?
+                    )?;
+
+                    *ty = Some(actual_type);
+                }
             }
         }
 
@@ -830,7 +1270,7 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
 
             // Condition must be type "bool"
             let expr_type = context.expression_type(expression)?;
-            if expr_type != "bool" {
+            if expr_type != Type::BOOL {
                 return Err(format!("'If' condition must be of type 'bool', but got '{}'", expr_type));
             }
 
@@ -842,29 +1282,344 @@ fn validate_expression(expression: &mut Expression, context: &mut Context, input
         }
 
         Expression::Identifier(ident) => {
-            if !context.is_primitive(ident) && !context.scopes.is_var_in_scope(ident) {
-                return Err(format!("Identifier '{}' not found in scope", ident));
+            *ident = context.resolve_identifier(ident)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Type of a member path that is assigned to. Swizzles cannot repeat components, and method calls are not allowed
+fn member_place_type(member: &Member, context: &Context) -> Result<Type, String> {
+    let mut place_type = None;
+
+    for item in &member.path {
+        match item {
+            IdentOrFunction::Ident(ident) => {
+                place_type = Some(match place_type {
+                    // First item is a variable. The rest are fields.
+                    None => context.scopes.var_type(ident)?.clone(),
+                    // Ensure that swizzle is op-assignment valid (can be more than length 1)
+                    Some(ty @ Type::Vector(..)) => glsl::vec::validate_swizzle_for_assignment(&ty, ident)?,
+                    Some(ty) => context.struct_field_type(&ty, ident)?.clone(),
+                });
+            }
+
+            // TODO: Is this always true? Or are there cases where this would be valid?
+            IdentOrFunction::Function(func) => {
+                return Err(format!("Cannot assign to '.' operator with function call '{}'", func.name));
             }
         }
     }
 
+    // Member paths always have at least two items
+    Ok(place_type.unwrap())
+}
+
+/// Arguments of `out` and `inout` parameters are written to, so they must be variables (or their fields,
+/// elements, and swizzles) that can be assigned. For methods, the first argument is the receiver
+fn check_written_arguments(function: &str, display_name: &str, arguments: &[Expression], method: bool, context: &Context) -> Result<(), String> {
+    for (index, argument) in arguments.iter().enumerate() {
+        let qualifier = match context.parameter_qualifier(function, index) {
+            FuncParamQualifier::In => continue,
+            FuncParamQualifier::Out => "out",
+            FuncParamQualifier::InOut => "inout",
+        };
+
+        // The reason continues the sentence
+        check_assignable(argument, context).map_err(|e| {
+            let mut reason = e.strip_prefix("Error: ").unwrap_or(&e).chars();
+            let reason = reason.next().map_or(String::new(), |first| first.to_lowercase().chain(reason).collect());
+
+            match (method, index) {
+                (true, 0) => format!("'{}' cannot be called here because it modifies its receiver ('{} self'): {}", display_name, qualifier, reason),
+                _ => format!("Argument {} of '{}' cannot be passed to an '{}' parameter, which writes to it: {}", index + 1, display_name, qualifier, reason),
+            }
+        })?;
+    }
+
     Ok(())
 }
 
+fn check_assignable(expression: &Expression, context: &Context) -> Result<(), String> {
+    match expression {
+        Expression::Identifier(_) => {}
+        Expression::Member(member) => { member_place_type(member, context)?; }
+        Expression::Unary { operator: UnaryOperator::Index(_), expr, .. } | Expression::Parenthesized(expr) => check_assignable(expr, context)?,
+        _ => return Err("Only variables, fields, array elements, and swizzles can be written to".to_owned()),
+    }
+
+    check_mutable(assigned_variable(expression).unwrap(), context)
+}
+
+/// Whether a variable can be written to, by assignment or as an `out` or `inout` argument
+fn check_mutable(variable: &str, context: &Context) -> Result<(), String> {
+    match context.scopes.var_mutability(variable) {
+        Ok(Mutability::Constant) if variable.starts_with("gl_") => Err(format!("Cannot assign to '{}' because it is a read-only built-in variable", variable)),
+        Ok(Mutability::Constant) if context.features.contains(variable) => Err(format!("Cannot assign to feature '{}' because it is set by the CPU", variable)),
+        Ok(Mutability::Constant) if context.is_uniform(variable) => Err(format!("Cannot assign to uniform '{}' because it is set by the CPU", variable)),
+        Ok(Mutability::Constant) if variable == "self" => Err("Cannot modify 'self' because it is passed as 'in self' (use 'inout self' instead)".to_owned()),
+        Ok(Mutability::Constant) => Err(format!("Cannot assign to '{}' because it is declared as constant", variable)),
+        Ok(Mutability::Immutable) => Err(format!("Cannot assign to '{}' because it is not mutable (declare it with 'let mut {}')", variable, variable)),
+        // `let mut (a, b)` is not valid, so `mut` goes on the binding
+        Ok(Mutability::ImmutablePattern) => Err(format!("Cannot assign to '{}' because it is not mutable (declare it with 'mut {}' in its pattern, like 'let (mut {}, b) = ...;')", variable, variable, variable)),
+        // Unknown variables are reported when their type is checked
+        _ => Ok(()),
+    }
+}
+
+/// The variable an assignment writes to (`a` in `a.b[0].c = ...`)
+fn assigned_variable(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Identifier(ident) => Some(ident),
+        Expression::Member(member) => match member.path.first() {
+            Some(IdentOrFunction::Ident(ident)) => Some(ident),
+            _ => None,
+        },
+        Expression::Unary { operator: UnaryOperator::Index(_), expr, .. } => assigned_variable(expr),
+        Expression::Parenthesized(expr) => assigned_variable(expr),
+        _ => None,
+    }
+}
+
+/// Tags only accept the arguments they understand
+fn check_tag_arguments(tag: &Tag, allowed: &[&str]) -> Result<(), String> {
+    let mut seen = HashSet::new();
+
+    for (key, _value) in &tag.arguments {
+        if !allowed.contains(&key.as_str()) {
+            return Err(format!("'{}' does not accept the argument '{}'", tag.kind, key));
+        }
+
+        if !seen.insert(key) {
+            return Err(format!("The argument '{}' was given to '{}' multiple times", key, tag.kind));
+        }
+    }
+
+    Ok(())
+}
+
+/// Uniform defaults are emitted before constants, so named constants are replaced by their values
+fn inline_constants(expression: &Expression, context: &Context) -> Expression {
+    let mut expression = expression.clone();
+    replace_constants(&mut expression, context);
+
+    expression
+}
+
+fn replace_constants(expression: &mut Expression, context: &Context) {
+    match expression {
+        Expression::Identifier(name) => {
+            if let Some(value) = context.constant_value(name) {
+                // Constants may refer to other constants
+                let value = inline_constants(value, context);
+                *expression = match value {
+                    Expression::Binary { .. } => Expression::Parenthesized(Box::new(value)),
+                    value => value,
+                };
+            }
+        }
+
+        Expression::Parenthesized(expr) => replace_constants(expr, context),
+        Expression::Unary { operator, expr, .. } => {
+            if let UnaryOperator::Index(index) = operator {
+                replace_constants(index, context);
+            }
+
+            replace_constants(expr, context);
+        }
+
+        Expression::Binary { lhs, rhs, .. } => {
+            replace_constants(lhs, context);
+            replace_constants(rhs, context);
+        }
+
+        Expression::ArrayConstructor { expressions, .. } | Expression::Tuple { expressions, .. } => {
+            expressions.iter_mut().for_each(|expr| replace_constants(expr, context));
+        }
+
+        Expression::FunctionCall(call) => call.parameters.iter_mut().for_each(|expr| replace_constants(expr, context)),
+
+        _ => {}
+    }
+}
+
+/// Uniform defaults are emitted before everything else, so they may only use literals, builtin constructors,
+/// and named constants (which are replaced by their values)
+fn check_uniform_default(expression: &Expression) -> Result<(), String> {
+    match expression {
+        Expression::Literal(_) => Ok(()),
+
+        Expression::Parenthesized(expr)
+        | Expression::Unary { operator: UnaryOperator::Negate, expr, .. }
+        | Expression::Unary { operator: UnaryOperator::Not, expr, .. } => check_uniform_default(expr),
+
+        // The right side of a cast is a type
+        Expression::Binary { lhs, operator: BinaryOperator::Cast, .. } => check_uniform_default(lhs),
+        Expression::Binary { lhs, rhs, .. } => {
+            check_uniform_default(lhs)?;
+            check_uniform_default(rhs)
+        }
+
+        Expression::ArrayConstructor { expressions, .. } => {
+            expressions.iter().try_for_each(|expr| check_uniform_default(expr))
+        }
+
+        Expression::FunctionCall(call) if glsl::vec::is_vec_constructor_or_type(&call.name)
+            || glsl::mat::is_mat_constructor_or_type(&call.name) => {
+            call.parameters.iter().try_for_each(|expr| check_uniform_default(expr))
+        }
+
+        _ => Err("Uniform defaults must be constant (only literals, named constants, vector and matrix constructors, arrays, and operators are allowed)".to_owned()),
+    }
+}
+
+/// Literal defaults (and each of their components) must be within the uniform's `min` and `max`
+fn check_default_range(default: &Expression, meta: &UniformMeta, ident: &str) -> Result<(), String> {
+    let mut components = Vec::new();
+    if meta.min.is_none() && meta.max.is_none() || !literal_components(default, &mut components) {
+        return Ok(());
+    }
+
+    let below = |value: f64| meta.min.map_or(false, |min| value < min);
+    let above = |value: f64| meta.max.map_or(false, |max| value > max);
+
+    match components.into_iter().find(|value| below(*value) || above(*value)) {
+        Some(value) => {
+            let range = match (meta.min, meta.max) {
+                (Some(min), Some(max)) => format!("from {} to {}", min, max),
+                (Some(min), None) => format!("at least {}", min),
+                (None, Some(max)) => format!("at most {}", max),
+                (None, None) => unreachable!(),
+            };
+
+            Err(format!("The default of uniform '{}' has the value {}, but it must be {} (the runtime would clamp it)", ident, value, range))
+        }
+
+        None => Ok(()),
+    }
+}
+
+/// Collects the numbers written in a default. Returns `false` if any part of it is computed
+fn literal_components(expression: &Expression, components: &mut Vec<f64>) -> bool {
+    match expression {
+        Expression::Literal(Literal::Int(i)) => components.push(*i as f64),
+        Expression::Literal(Literal::UInt(u)) => components.push(*u as f64),
+        Expression::Literal(Literal::Float(f)) => components.push(f.to_string().parse::<f64>().unwrap()),
+        Expression::Literal(Literal::Double(d)) => components.push(*d),
+
+        Expression::Parenthesized(expr) => return literal_components(expr, components),
+
+        Expression::Unary { operator: UnaryOperator::Negate, expr, .. } => {
+            let mut negated = Vec::new();
+            if !literal_components(expr, &mut negated) {
+                return false;
+            }
+
+            components.extend(negated.into_iter().map(|value| -value));
+        }
+
+        Expression::ArrayConstructor { expressions, .. } => {
+            return expressions.iter().all(|expr| literal_components(expr, components));
+        }
+
+        Expression::FunctionCall(call) if glsl::vec::is_vec_constructor_or_type(&call.name)
+            || glsl::mat::is_mat_constructor_or_type(&call.name) => {
+            return call.parameters.iter().all(|expr| literal_components(expr, components));
+        }
+
+        _ => return false,
+    }
+
+    true
+}
+
+/// A non-negative integer tag argument, like `binding = 2`
+fn tag_index(tag: &Tag, key: &str, ident: &str) -> Result<Option<u32>, String> {
+    match tag.argument(key) {
+        Some(TagValue::Literal(Literal::Int(index))) if *index >= 0 => Ok(Some(*index as u32)),
+        Some(_) => Err(format!("The {} of '{}' must be a non-negative integer", key, ident)),
+        None => Ok(None),
+    }
+}
+
+/// Reads the annotations of `@uniform(min = 0., max = 1., step = 0.1, label = "..", widget = "..", semantic = "..")`
+fn uniform_meta(tag: &Tag, ty: &Type, ident: &str) -> Result<UniformMeta, String> {
+    let number = |key: &str| match tag.argument(key) {
+        Some(TagValue::Literal(Literal::Int(i))) => Ok(Some(*i as f64)),
+        // Widening through the shortest representation keeps `0.1` from becoming `0.10000000149011612`
+        Some(TagValue::Literal(Literal::Float(f))) => Ok(Some(f.to_string().parse::<f64>().unwrap())),
+        Some(_) => Err(format!("The {} of '{}' must be a number", key, ident)),
+        None => Ok(None),
+    };
+
+    let string = |key: &str| match tag.argument(key) {
+        Some(TagValue::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(format!("The {} of '{}' must be a string", key, ident)),
+        None => Ok(None),
+    };
+
+    let meta = UniformMeta {
+        min: number("min")?,
+        max: number("max")?,
+        step: number("step")?,
+        label: string("label")?,
+        widget: string("widget")?,
+        semantic: string("semantic")?,
+    };
+
+    // Ranges apply to each component (or element) of numeric types
+    let numeric = match ty {
+        Type::Array(element, _) => element.is_numeric(),
+        _ => ty.is_numeric(),
+    };
+
+    if !numeric && (meta.min.is_some() || meta.max.is_some() || meta.step.is_some()) {
+        return Err(format!("Uniform '{}' has type '{}', so it cannot have a min, max, or step", ident, ty));
+    }
+
+    if let (Some(min), Some(max)) = (meta.min, meta.max) {
+        if min > max {
+            return Err(format!("Uniform '{}' has a min ({}) greater than its max ({})", ident, min, max));
+        }
+    }
+
+    if let Some(step) = meta.step {
+        if step <= 0. {
+            return Err(format!("Uniform '{}' must have a positive step (got {})", ident, step));
+        }
+    }
+
+    let valid_widget = match meta.widget.as_deref() {
+        None => true,
+        Some("slider") => numeric && meta.min.is_some() && meta.max.is_some(),
+        Some("drag") => numeric,
+        Some("color") => *ty == Type::Vector(Scalar::Float, 3) || *ty == Type::Vector(Scalar::Float, 4),
+        Some("checkbox") => *ty == Type::BOOL,
+        Some(widget) => {
+            return Err(format!("Uniform '{}' has an unknown widget '{}' (expected 'slider', 'drag', 'color', or 'checkbox')", ident, widget));
+        }
+    };
+
+    if !valid_widget {
+        return Err(format!("The '{}' widget cannot be used by uniform '{}' of type '{}' (sliders also require a min and max)",
+                            meta.widget.unwrap(), ident, ty));
+    }
+
+    Ok(meta)
+}
+
 pub fn translate(ast: &AST, context: &Context) -> String {
     use template::*;
 
-    // Unfortunately, GLSL requires functions to be declared in order of use
-    // sdf-lang can compensate for this by forward declaring all functions
-    // OR sdf-lang can also require forced ordering
-
+    // GLSL requires everything to be declared before it is used, so items were sorted during validation
     let mut glsl = String::new();
 
     if context.shader_type != crate::parse::context::ShaderType::Library {   
-        // TODO: Allow user to specify version
-        glsl.push_str("#version 450 core\n\n");
+        glsl.push_str(&format!("#version {} core\n\n", context.options.glsl_version()));
     }
     glsl.push_str(&translate_uniforms(context.uniforms()));
+    glsl.push_str(&translate_textures(context.textures()));
     glsl.push_str(&translate_outs(context.outs()));
 
     // TODO: Allow let statements at global scope for global variables
@@ -872,7 +1627,10 @@ pub fn translate(ast: &AST, context: &Context) -> String {
         // `Item`s always have global scopes
         match item {
             Item::Features { .. } 
-            | Item::Import { .. } => {
+            | Item::Uniforms { .. }
+            | Item::Import { .. }
+            | Item::Use { .. }
+            | Item::TypeAlias { .. } => {
                 // Nothing to do here
             }
 
@@ -880,12 +1638,11 @@ pub fn translate(ast: &AST, context: &Context) -> String {
                 glsl.push_str(&translate_const(constant));
             }
 
-            Item::Struct { name, fields } => {
+            Item::Struct { name, fields, .. } => {
                 glsl.push_str(&translate_structure(name, fields));
             }
 
-            Item::Function { name, parameters, return_type, statements } => {
-                // TODO: Body statements
+            Item::Function { name, parameters, return_type, statements, .. } => {
                 glsl.push_str(&translate_function(name, parameters, &return_type, statements));
             }
 
@@ -893,10 +1650,13 @@ pub fn translate(ast: &AST, context: &Context) -> String {
                 // glsl.push_str(&translate_scene(name, statements));
             }
 
+            Item::Cfg { .. } => unreachable!("cfg items are resolved before validation"),
+            Item::Lint { .. } => unreachable!("lint attributes are resolved before validation"),
+
             Item::Implementation { struct_name: _, functions } => {
                 for function in functions {
                     match function {
-                        Item::Function { name, parameters, return_type, statements } => {
+                        Item::Function { name, parameters, return_type, statements, .. } => {
                             glsl.push_str(&translate_function(name, parameters, &return_type, statements));
                         }
 
@@ -908,4 +1668,5 @@ pub fn translate(ast: &AST, context: &Context) -> String {
     }
 
     glsl
-}
\ No newline at end of file
+}
+
@@ -0,0 +1,193 @@
+use crate::parse::ast::*;
+use crate::parse::Input;
+
+use super::reach::references;
+
+use std::collections::HashMap;
+
+// Item ordering
+//
+// GLSL requires everything to be declared before it is used, but `.sdf` files may declare items in any
+// order. Before a file is validated, its structs, type aliases, and constants are sorted so each comes
+// after what it depends on, and functions are moved after them. Function signatures are declared before
+// any body is validated (see `validate_ast`), and once calls are resolved, functions are sorted so each
+// comes after the functions it calls.
+//
+// GLSL does not allow recursion, so cycles in the call graph are errors (and otherwise, an order always exists).
+
+/// Position of each kind of item after sorting (items of the same rank keep their order)
+fn rank(item: &Item) -> usize {
+    match item {
+        Item::Import { .. } | Item::Use { .. } => 0,
+        Item::TypeAlias { .. } | Item::Struct { .. } | Item::Constant(_) => 1,
+        // Together, so uniform locations follow the order they were written in
+        Item::Features { .. } | Item::Uniforms { .. } => 2,
+        _ => 3,
+    }
+}
+
+/// The name a declaration is referred to by, for those that are sorted by dependency
+fn declared_name(item: &Item) -> Option<&str> {
+    match item {
+        Item::TypeAlias { name, .. } | Item::Struct { name, .. } => Some(name),
+        Item::Constant(constant) => Some(&constant.ident),
+        _ => None,
+    }
+}
+
+/// Move declarations before their uses. Called on a single file before it is validated
+pub fn sort_items(ast: &mut AST, input: &Input) -> Result<(), String> {
+    ast.sort_by_key(rank);
+
+    let start = ast.iter().position(|item| rank(item) >= 1).unwrap_or(ast.len());
+    let end = ast.iter().position(|item| rank(item) > 1).unwrap_or(ast.len());
+    let declarations: Vec<Item> = ast.drain(start..end).collect();
+
+    let indices: HashMap<&str, usize> = declarations.iter().enumerate()
+        .filter_map(|(index, item)| declared_name(item).map(|name| (name, index)))
+        .collect();
+
+    // Declaration -> Declarations it depends on (in the order they were written)
+    let dependencies: Vec<Vec<usize>> = declarations.iter().map(|item| {
+        let found = references(item);
+        let mut dependencies: Vec<usize> = found.names.iter().filter_map(|name| indices.get(name.as_str()).copied()).collect();
+        dependencies.sort();
+        dependencies
+    }).collect();
+
+    let mut sorted = Vec::new();
+    let mut visited = vec![false; declarations.len()];
+
+    for index in 0..declarations.len() {
+        visit(index, &dependencies, &mut visited, &mut Vec::new(), &mut sorted).map_err(|cycle| {
+            let path: Vec<String> = cycle.iter().map(|index| format!("'{}'", declared_name(&declarations[*index]).unwrap())).collect();
+            let item = &declarations[cycle[0]];
+
+            let location = match item {
+                Item::TypeAlias { span, .. } | Item::Struct { span, .. } => format!("{}\n", input.evaluate_span(*span)),
+                Item::Constant(constant) => format!("{}\n", input.evaluate_span(constant.value.span)),
+                _ => String::new(),
+            };
+
+            format!("{}{} depends on itself ({})", location, path[0], path.join(" -> "))
+        })?;
+    }
+
+    let mut declarations: Vec<Option<Item>> = declarations.into_iter().map(Some).collect();
+    ast.splice(start..start, sorted.into_iter().map(|index| declarations[index].take().unwrap()));
+
+    Ok(())
+}
+
+/// Depth-first topological sort. A cycle is returned as the path from the first item back to itself
+fn visit(index: usize, dependencies: &[Vec<usize>], visited: &mut [bool], path: &mut Vec<usize>, sorted: &mut Vec<usize>) -> Result<(), Vec<usize>> {
+    if let Some(position) = path.iter().position(|other| *other == index) {
+        let mut cycle = path[position..].to_vec();
+        cycle.push(index);
+        return Err(cycle);
+    }
+
+    if visited[index] {
+        return Ok(());
+    }
+
+    path.push(index);
+    for dependency in &dependencies[index] {
+        visit(*dependency, dependencies, visited, path, sorted)?;
+    }
+    path.pop();
+
+    visited[index] = true;
+    sorted.push(index);
+
+    Ok(())
+}
+
+/// Functions of a validated file as (GLSL name, name to display, span, item)
+fn functions<'a>(ast: &'a AST, prefix: &str) -> Vec<(String, String, Span, &'a Item)> {
+    let mut functions = Vec::new();
+
+    for item in ast {
+        match item {
+            Item::Function { name, span, .. } => {
+                let display = name.strip_prefix(prefix).unwrap_or(name).to_owned();
+                functions.push((name.clone(), display, *span, item));
+            }
+
+            Item::Implementation { struct_name, functions: methods } => {
+                let method_prefix = format!("__{}__", struct_name);
+
+                for method in methods {
+                    if let Item::Function { name, span, .. } = method {
+                        let display = format!("{}.{}", struct_name, name.strip_prefix(&method_prefix).unwrap_or(name));
+                        functions.push((name.clone(), display, *span, method));
+                    }
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    functions
+}
+
+/// Sort the functions of a validated file so each comes after the functions it calls, rejecting recursion.
+/// `prefix` is the module prefix of the file
+pub fn sort_functions(ast: &mut AST, prefix: &str, input: &Input) -> Result<(), String> {
+    let prefix = if prefix.is_empty() { String::new() } else { format!("{}__", prefix) };
+
+    // Each method is sorted on its own, within an implementation of its struct
+    let start = ast.iter().position(|item| matches!(item, Item::Function { .. } | Item::Implementation { .. })).unwrap_or(ast.len());
+    let mut items = Vec::new();
+    for item in ast.drain(start..) {
+        match item {
+            Item::Implementation { struct_name, functions } => {
+                items.extend(functions.into_iter().map(|function| Item::Implementation { struct_name: struct_name.clone(), functions: vec![function] }));
+            }
+            item => items.push(item),
+        }
+    }
+
+    let functions = functions(&items, &prefix);
+    let indices: HashMap<&str, usize> = functions.iter().enumerate().map(|(index, (name, ..))| (name.as_str(), index)).collect();
+
+    // Function -> Functions of this file that it calls. Imported functions cannot call back into the file
+    let calls: Vec<Vec<usize>> = functions.iter().map(|(.., item)| {
+        let mut calls: Vec<usize> = references(item).calls.iter().filter_map(|name| indices.get(name.as_str()).copied()).collect();
+        calls.sort();
+        calls
+    }).collect();
+
+    let mut sorted = Vec::new();
+    let mut visited = vec![false; functions.len()];
+
+    for index in 0..functions.len() {
+        visit(index, &calls, &mut visited, &mut Vec::new(), &mut sorted).map_err(|cycle| {
+            let path: Vec<String> = cycle.iter().map(|index| format!("'{}'", functions[*index].1)).collect();
+            let (_, display, span, _) = &functions[cycle[0]];
+
+            format!("{}\nFunction '{}' is recursive ({}), which GLSL does not allow", input.evaluate_span(*span), display, path.join(" -> "))
+        })?;
+    }
+
+    // Generated tuple structs are among the functions, and must stay before all of them
+    let (functions, others): (Vec<_>, Vec<_>) = items.into_iter().partition(|item| matches!(item, Item::Function { .. } | Item::Implementation { .. }));
+    let mut functions: Vec<Option<Item>> = functions.into_iter().map(Some).collect();
+    ast.extend(others);
+
+    for index in sorted {
+        match functions[index].take().unwrap() {
+            // Consecutive methods of a struct share an implementation
+            Item::Implementation { struct_name, functions: methods } => match ast.last_mut() {
+                Some(Item::Implementation { struct_name: previous, functions: previous_methods }) if *previous == struct_name => {
+                    previous_methods.extend(methods);
+                }
+                _ => ast.push(Item::Implementation { struct_name, functions: methods }),
+            },
+            function => ast.push(function),
+        }
+    }
+
+    Ok(())
+}
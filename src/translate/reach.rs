@@ -0,0 +1,323 @@
+use crate::parse::ast::*;
+use crate::parse::context::{Context, ShaderType};
+use crate::parse::types::Type;
+
+use std::collections::{HashMap, HashSet};
+
+// Dead code elimination
+//
+// Runs once the whole shader (including its libraries) was validated and spliced together, so every
+// name is already its GLSL name. Functions, structs, and constants that cannot be reached from `main`
+// are removed, so only what the shader uses is emitted. Uniforms, textures, and features that are
+// never read are marked inactive in the `Context`, and the runtime does not bind them.
+//
+// References are collected by name without regard to scope, so a local that shadows a global keeps
+// the global alive. This only ever keeps too much.
+
+/// Remove the items that `main` does not depend on. Libraries keep everything, since any item may be used by an importer
+pub fn eliminate_dead_code(ast: &mut AST, context: &mut Context) {
+    if context.shader_type == ShaderType::Library {
+        return;
+    }
+
+    // Item name -> What it refers to
+    let mut dependencies: HashMap<&str, References> = HashMap::new();
+
+    for item in ast.iter() {
+        match item {
+            Item::Function { name, .. } => { dependencies.insert(name, references(item)); }
+
+            Item::Implementation { functions, .. } => {
+                for function in functions {
+                    if let Item::Function { name, .. } = function {
+                        dependencies.insert(name, references(function));
+                    }
+                }
+            }
+
+            Item::Struct { name, .. } => { dependencies.insert(name, references(item)); }
+            Item::Constant(constant) => { dependencies.insert(&constant.ident, references(item)); }
+
+            _ => {}
+        }
+    }
+
+    // Without an entry point, there is nothing to measure reachability from
+    if !dependencies.contains_key("main") {
+        return;
+    }
+
+    let mut reachable: HashSet<String> = HashSet::new();
+    let mut pending = vec!["main".to_owned()];
+
+    while let Some(name) = pending.pop() {
+        if !reachable.insert(name.clone()) {
+            continue;
+        }
+
+        // Globals other than items (uniforms, features, etc.) have no dependencies
+        if let Some(references) = dependencies.get(name.as_str()) {
+            let names = references.calls.iter().chain(references.names.iter());
+            pending.extend(names.filter(|name| !reachable.contains(*name)).cloned());
+        }
+    }
+
+    ast.retain_mut(|item| match item {
+        Item::Function { name, .. } | Item::Struct { name, .. } => reachable.contains(name),
+        Item::Constant(constant) => reachable.contains(&constant.ident),
+
+        Item::Implementation { functions, .. } => {
+            functions.retain(|function| matches!(function, Item::Function { name, .. } if reachable.contains(name)));
+            !functions.is_empty()
+        }
+
+        _ => true,
+    });
+
+    context.deactivate_unused(&reachable);
+}
+
+/// Names referred to by an item
+#[derive(Debug, Default)]
+pub struct References {
+    /// Functions and methods it calls
+    pub calls: HashSet<String>,
+    /// Types, constants, and globals
+    pub names: HashSet<String>,
+}
+
+/// Everything a function, struct, type alias, or constant refers to. Before validation, these are the names
+/// as written, and afterwards they are GLSL names
+pub fn references(item: &Item) -> References {
+    let mut found = References::default();
+
+    match item {
+        Item::Function { parameters, return_type, statements, .. } => {
+            for (_qualifier, _name, ty) in parameters {
+                type_specifier(ty, &mut found);
+            }
+
+            type_specifier(return_type, &mut found);
+            block(statements, &mut found);
+        }
+
+        Item::Struct { fields, .. } => {
+            for (_field, ty, default) in fields {
+                type_specifier(ty, &mut found);
+
+                if let Some(default) = default {
+                    expression(default, &mut found);
+                }
+            }
+        }
+
+        Item::TypeAlias { ty, .. } => type_specifier(ty, &mut found),
+
+        Item::Constant(constant) => {
+            type_specifier(&constant.ty, &mut found);
+            expression(&constant.value.expression, &mut found);
+        }
+
+        _ => {}
+    }
+
+    found
+}
+
+/// Everything an expression refers to
+pub fn expression_references(expr: &Expression) -> References {
+    let mut found = References::default();
+    expression(expr, &mut found);
+
+    found
+}
+
+fn block(statements: &[Statement], found: &mut References) {
+    for statement in statements {
+        match statement {
+            Statement::Let { ty, expression: value, .. } => {
+                if let Some(ty) = ty {
+                    type_specifier(ty, found);
+                }
+
+                if let Some(value) = value {
+                    expression(&value.expression, found);
+                }
+            }
+
+            Statement::LetConstructor { constructor, .. } => {
+                found.names.insert(constructor.ty.clone());
+
+                for (_field, value) in &constructor.fields {
+                    expression(&value.expression, found);
+                }
+            }
+
+            Statement::LetPattern { pattern: p, ty, expression: value, .. } => {
+                if let Some(ty) = ty {
+                    type_specifier(ty, found);
+                }
+
+                pattern(p, found);
+                expression(&value.expression, found);
+            }
+
+            Statement::Constant(constant) => {
+                type_specifier(&constant.ty, found);
+                expression(&constant.value.expression, found);
+            }
+
+            Statement::Assignment { lhs, expression: value, .. } => {
+                expression(&lhs.expression, found);
+                expression(&value.expression, found);
+            }
+
+            Statement::Return { expression: value, .. } => {
+                if let Some(value) = value {
+                    expression(&value.expression, found);
+                }
+            }
+
+            Statement::For { from, to, block: body, .. } => {
+                expression(&from.expression, found);
+                expression(&to.expression, found);
+                block(body, found);
+            }
+
+            Statement::While { condition, block: body, .. } => {
+                expression(&condition.expression, found);
+                block(body, found);
+            }
+
+            Statement::Expression { expression: value, .. } => expression(value, found),
+            Statement::Block { statements, .. } => block(statements, found),
+
+            Statement::Continue(_) | Statement::Break(_) => {}
+
+            Statement::Cfg { .. } | Statement::IfConst { .. } => unreachable!("cfg statements are resolved before items are ordered"),
+        }
+    }
+}
+
+fn expression(expr: &Expression, found: &mut References) {
+    match expr {
+        Expression::Literal(_) => {}
+        Expression::Identifier(name) => { found.names.insert(name.clone()); }
+        Expression::Parenthesized(expr) => expression(expr, found),
+
+        Expression::ArrayConstructor { expressions, ty } | Expression::Tuple { expressions, ty } => {
+            if let Some(ty) = ty {
+                type_name(ty, found);
+            }
+
+            for expr in expressions {
+                expression(expr, found);
+            }
+        }
+
+        // The right side of a cast is an identifier naming the type
+        Expression::Binary { lhs, rhs, ty, .. } => {
+            expression(lhs, found);
+            expression(rhs, found);
+
+            if let Some(ty) = ty {
+                type_name(ty, found);
+            }
+        }
+
+        Expression::Unary { operator, expr, .. } => {
+            if let UnaryOperator::Index(index) = operator {
+                expression(index, found);
+            }
+
+            expression(expr, found);
+        }
+
+        Expression::FunctionApply(FunctionApply { name, parameters, .. }) | Expression::FunctionCall(FunctionCall { name, parameters, .. }) => {
+            found.calls.insert(name.clone());
+
+            for expr in parameters {
+                expression(expr, found);
+            }
+        }
+
+        // Only the first identifier can be a global (the rest are fields)
+        Expression::Member(member) => {
+            for (i, item) in member.path.iter().enumerate() {
+                match item {
+                    IdentOrFunction::Ident(name) => {
+                        if i == 0 {
+                            found.names.insert(name.clone());
+                        }
+                    }
+
+                    IdentOrFunction::Function(call) => {
+                        found.calls.insert(call.name.clone());
+
+                        for expr in &call.parameters {
+                            expression(expr, found);
+                        }
+                    }
+                }
+            }
+        }
+
+        Expression::If { expression: condition, if_block, else_block, else_if_block, .. } => {
+            expression(condition, found);
+            block(if_block, found);
+
+            if let Some(else_block) = else_block {
+                block(else_block, found);
+            }
+
+            if let Some(else_if) = else_if_block {
+                expression(else_if, found);
+            }
+        }
+    }
+}
+
+fn pattern(p: &Pattern, found: &mut References) {
+    match p {
+        Pattern::Binding { ty, .. } => {
+            if let Some(ty) = ty {
+                type_specifier(ty, found);
+            }
+        }
+
+        Pattern::Tuple(patterns) | Pattern::Vector(patterns) => {
+            for p in patterns {
+                pattern(p, found);
+            }
+        }
+
+        Pattern::Struct { name, fields, .. } => {
+            found.names.insert(name.clone());
+
+            for (_field, p) in fields {
+                pattern(p, found);
+            }
+        }
+    }
+}
+
+fn type_specifier(ty: &TypeSpecifier, found: &mut References) {
+    match ty {
+        TypeSpecifier::Identifier(name) | TypeSpecifier::Array { ty: name, .. } => { found.names.insert(name.clone()); }
+
+        // Only before validation
+        TypeSpecifier::Tuple(elements) => {
+            for ty in elements {
+                type_specifier(ty, found);
+            }
+        }
+    }
+}
+
+fn type_name(ty: &Type, found: &mut References) {
+    match ty {
+        Type::Struct(name) => { found.names.insert(name.clone()); }
+        Type::Array(ty, _) => type_name(ty, found),
+        _ => {}
+    }
+}
@@ -0,0 +1,78 @@
+use super::template::{translate_expression, translate_type_specifier};
+use crate::parse::context::{Context, UniformBinding};
+
+// Reflection
+//
+// Describes everything a host must provide to a compiled shader as JSON: uniforms (with their
+// locations, defaults, and `@uniform(...)` annotations), texture bindings, and output locations.
+// Types and defaults are written as GLSL. Inactive uniforms and textures are not read by the shader
+// (and are left out of the GLSL), so hosts should not bind them.
+
+/// JSON description of the shader's interface
+pub fn reflect(context: &Context) -> String {
+    let uniforms: Vec<String> = context.uniforms().iter().map(reflect_uniform).collect();
+
+    let textures: Vec<String> = context.textures().iter().map(|texture| {
+        format!("{{ \"name\": {}, \"type\": {}, \"binding\": {}, \"active\": {} }}",
+            string(&texture.name), string(&texture.ty), texture.binding, texture.active)
+    }).collect();
+
+    let outputs: Vec<String> = context.outs().iter().map(|out| {
+        format!("{{ \"name\": {}, \"type\": {}, \"location\": {} }}", 
+            string(&out.name), string(&translate_type_specifier(None, &out.ty)), out.location)
+    }).collect();
+
+    format!("{{\n  \"uniforms\": {},\n  \"textures\": {},\n  \"outputs\": {}\n}}\n", list(uniforms), list(textures), list(outputs))
+}
+
+fn reflect_uniform(uniform: &UniformBinding) -> String {
+    let meta = &uniform.meta;
+
+    let fields = vec![
+        ("name", string(&uniform.name)),
+        ("type", string(&translate_type_specifier(None, &uniform.ty))),
+        ("location", uniform.location.to_string()),
+        ("active", uniform.active.to_string()),
+        ("default", optional(uniform.default.as_ref().map(|d| string(&translate_expression(d))))),
+        ("min", optional(meta.min.map(|n| n.to_string()))),
+        ("max", optional(meta.max.map(|n| n.to_string()))),
+        ("step", optional(meta.step.map(|n| n.to_string()))),
+        ("label", optional(meta.label.as_ref().map(|s| string(s)))),
+        ("widget", optional(meta.widget.as_ref().map(|s| string(s)))),
+        ("semantic", optional(meta.semantic.as_ref().map(|s| string(s)))),
+    ];
+
+    let fields: Vec<String> = fields.into_iter().map(|(key, value)| format!("\"{}\": {}", key, value)).collect();
+
+    format!("{{ {} }}", fields.join(", "))
+}
+
+fn list(values: Vec<String>) -> String {
+    if values.is_empty() {
+        "[]".to_owned()
+    } else {
+        format!("[\n    {}\n  ]", values.join(",\n    "))
+    }
+}
+
+fn optional(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "null".to_owned())
+}
+
+fn string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped.push('"');
+    escaped
+}
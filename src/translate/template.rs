@@ -1,27 +1,46 @@
 use crate::parse::ast::*;
+use crate::parse::context::{OutputBinding, TextureBinding, UniformBinding};
 
-use std::collections::HashSet;
+// Note that uniform initializers require GLSL 1.20+ (not GLSL ES)
+pub fn translate_uniforms(uniforms: &Vec<UniformBinding>) -> String {
+    let mut glsl = String::new();
+
+    for uniform in uniforms.iter().filter(|u| u.active) {
+        glsl.push_str(&format!("layout(location = {}) uniform {}", uniform.location, translate_type_specifier(Some(&uniform.name), &uniform.ty)));
+
+        if let Some(default) = &uniform.default {
+            glsl.push_str(&format!(" = {}", translate_expression(default)));
+        }
+
+        glsl.push_str(";\n");
+    }
 
-// TODO: Assign uniforms their default value (type checked)
-pub fn translate_uniforms(uniforms: &HashSet<(String, TypeSpecifier)>) -> String {
+    if uniforms.iter().any(|u| u.active) {
+        glsl.push('\n');
+    }
+
+    glsl
+}
+
+pub fn translate_textures(textures: &Vec<TextureBinding>) -> String {
     let mut glsl = String::new();
 
-    for (index, (name, ty)) in uniforms.iter().enumerate() {
-        glsl.push_str(&format!("layout(location = {}) uniform {};\n", index, translate_type_specifier(Some(name), &ty)));
+    for texture in textures.iter().filter(|t| t.active) {
+        glsl.push_str(&format!("layout(binding = {}) uniform {} {};\n", texture.binding, texture.ty, texture.name));
     }
 
-    if uniforms.len() >= 1 {
+    if textures.iter().any(|t| t.active) {
         glsl.push('\n');
     }
 
     glsl
 }
 
-pub fn translate_outs(outs: &HashSet<(String, TypeSpecifier)>) -> String {
+pub fn translate_outs(outs: &Vec<OutputBinding>) -> String {
     let mut glsl = String::new();
 
-    for (index, (name, ty)) in outs.iter().enumerate() {
-        glsl.push_str(&format!("layout(location = {}) out {};\n", index, translate_type_specifier(Some(name), &ty)));
+    for out in outs {
+        glsl.push_str(&format!("layout(location = {}) out {};\n", out.location, translate_type_specifier(Some(&out.name), &out.ty)));
     }
 
     if outs.len() >= 1 {
@@ -44,6 +63,7 @@ pub fn translate_type_specifier(variable: Option<&str>, t: &TypeSpecifier) -> St
     match t {
         TypeSpecifier::Identifier(_ident) => {},
         TypeSpecifier::Array { ty: _, size } => glsl.push_str(&format!("[{}]", size)),
+        TypeSpecifier::Tuple(_) => unreachable!("tuple types are replaced during validation"),
     }
 
     glsl
@@ -57,6 +77,7 @@ pub fn translate_const(constant: &ConstDeclaration) -> String {
         TypeSpecifier::Array { ty, size } => {
             (ty.clone(), format!("{}[{}]", &constant.ident, size))
         }
+        TypeSpecifier::Tuple(_) => unreachable!("tuple types are replaced during validation"),
     };
     //       const vec4 vert[x] = ...
     format!("const {} {} = {};\n\n", ty, ident, translate_expression(&constant.value.expression))
@@ -104,9 +125,12 @@ pub fn translate_function(name: &str, parameters: &Vec<(Option<FuncParamQualifie
     for nested_statement in statements {
         // Tagged variables are placed in global scope (required by GLSL)
         match nested_statement {
-            Statement::Let { tag, .. } => {
-                if let Some(_tag) = tag {
-                    continue;
+            Statement::Let { tag, expression, .. } => {
+                if let Some(tag) = tag {
+                    // Initialized outputs are assigned where they were declared
+                    if tag.kind != TagKind::Out || expression.is_none() {
+                        continue;
+                    }
                 }
             }
             _ => {},
@@ -135,6 +159,35 @@ pub fn translate_scene(name: &str, statements: &Vec<Statement>) -> String {
     glsl
 }
 
+/// Declares every binding of a pattern from `source` (all on the same line)
+fn translate_pattern(pattern: &Pattern, source: String, glsl: &mut String) {
+    match pattern {
+        Pattern::Binding { ident, ty, .. } => {
+            if ident != "_" {
+                glsl.push_str(&format!("; {} = {}", translate_type_specifier(Some(ident), ty.as_ref().unwrap()), source));
+            }
+        }
+
+        Pattern::Tuple(patterns) => {
+            for (i, pattern) in patterns.iter().enumerate() {
+                translate_pattern(pattern, format!("{}._{}", source, i), glsl);
+            }
+        }
+
+        Pattern::Vector(patterns) => {
+            for (pattern, component) in patterns.iter().zip("xyzw".chars()) {
+                translate_pattern(pattern, format!("{}.{}", source, component), glsl);
+            }
+        }
+
+        Pattern::Struct { name: _, fields, rest: _ } => {
+            for (field, pattern) in fields {
+                translate_pattern(pattern, format!("{}.{}", source, field), glsl);
+            }
+        }
+    }
+}
+
 pub fn translate_statement(statement: &Statement) -> String {
     let mut glsl = String::new();
 
@@ -188,7 +241,7 @@ pub fn translate_statement(statement: &Statement) -> String {
             glsl.push_str("\t}");
         }
 
-        Statement::Return { expression: expr } => {
+        Statement::Return { expression: expr, .. } => {
             if let Some(ret_expr) = expr {
                 glsl.push_str(&format!("return {}", translate_expression(&ret_expr.expression)));
             } else {
@@ -196,6 +249,11 @@ pub fn translate_statement(statement: &Statement) -> String {
             }
         }
 
+        // Outputs are declared globally, so only the initial value is assigned
+        Statement::Let { tag: Some(tag), ident, expression: Some(assignment), .. } if tag.kind == TagKind::Out => {
+            glsl.push_str(&format!("{} = {}", ident, translate_expression(&assignment.expression)));
+        }
+
         // TODO: Tagged variables should not be re-included here (handled elsewhere for global scope)
         Statement::Let { ident, ty, expression: expr, .. } => {           
             if ty.is_none() {
@@ -211,6 +269,7 @@ pub fn translate_statement(statement: &Statement) -> String {
                 TypeSpecifier::Array { ty, size } => {
                     (ty.clone(), format!("{}[{}]", ident, size))
                 }
+                TypeSpecifier::Tuple(_) => unreachable!("tuple types are replaced during validation"),
             };
 
             glsl.push_str(&format!("{} {}", ty, ident));
@@ -220,8 +279,16 @@ pub fn translate_statement(statement: &Statement) -> String {
             }
         }
         
+        // The value is stored in a temporary, then each binding is declared from its fields
+        Statement::LetPattern { pattern, ty, expression, temp } => {
+            glsl.push_str(&format!("{} = {}", 
+                translate_type_specifier(Some(temp), ty.as_ref().unwrap()), translate_expression(&expression.expression)));
+
+            translate_pattern(pattern, temp.clone(), &mut glsl);
+        }
+
         // Defaults and ordering will be handled while parsing
-        Statement::LetConstructor { ident, constructor } => {
+        Statement::LetConstructor { ident, constructor, .. } => {
             let mut fields = String::new();
 
             for (_field_name, expr) in &constructor.fields {
@@ -271,6 +338,18 @@ pub fn translate_statement(statement: &Statement) -> String {
         Statement::Expression{expression, span: _} => {
             glsl.push_str(&translate_expression(expression));
         }
+
+        Statement::Block { statements, span: _ } => {
+            glsl.push_str("{\n");
+
+            for block_stmt in statements {
+                glsl.push_str(&format!("\t\t{}", translate_statement(block_stmt)));
+            }
+
+            glsl.push_str("\t}");
+        }
+
+        Statement::Cfg { .. } | Statement::IfConst { .. } => unreachable!("cfg statements are resolved before validation"),
     }
 
     // Don't place ';' after statement blocks
@@ -289,8 +368,13 @@ pub fn translate_expression(expr: &Expression) -> String {
     let mut glsl = String::new();
     
     match expr {
+        Expression::Tuple { expressions, ty } => {
+            let elements: Vec<String> = expressions.iter().map(|e| translate_expression(e)).collect();
+            glsl.push_str(&format!("{}({})", ty.as_ref().unwrap(), elements.join(", ")));
+        }
+
         Expression::ArrayConstructor { expressions, ty } => {
-            glsl.push_str(&format!("{}[](", ty));
+            glsl.push_str(&format!("{}[](", ty.as_ref().unwrap()));
             for item in expressions {
                 glsl.push_str(&translate_expression(item));
                 glsl.push_str(", ");